@@ -0,0 +1,146 @@
+//! Named cellular automata along with the colors that they're traditionally drawn with.  Some of
+//! them are Generations rules that run on the usual engines, while others like Wireworld have
+//! transitions that can't be written as a rulestring and are stepped here.
+//!
+//! Wireworld's states are stored as: empty = `Dead`, electron head = `Alive`, electron tail =
+//! `Dying(2)`, and conductor = `Dying(3)`.
+
+use std::fmt;
+
+use crate::{rule::Rule, topology::Topology, Board, Cell};
+
+const WIREWORLD_TAIL: Cell = Cell::Dying(2);
+const WIREWORLD_CONDUCTOR: Cell = Cell::Dying(3);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Automaton {
+    /// Electrons travel along conductors, leaving tails behind them
+    Wireworld,
+    /// `B2/S/C3`: cells that fire are refractory for a generation before they can fire again
+    BriansBrain,
+}
+
+impl Automaton {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let normalized: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "wireworld" => Ok(Automaton::Wireworld),
+            "briansbrain" => Ok(Automaton::BriansBrain),
+            _ => Err(format!("Unknown automaton: \"{}\"", name)),
+        }
+    }
+
+    /// Returns the Generations rule that the automaton is equivalent to, or `None` if it has to be
+    /// stepped with `Automaton::step`
+    pub fn rule(self) -> Option<Rule> {
+        match self {
+            Automaton::Wireworld => None,
+            Automaton::BriansBrain => Some(Rule::parse("B2/S/C3").unwrap()),
+        }
+    }
+
+    pub fn states(self) -> u8 {
+        match self {
+            Automaton::Wireworld => 4,
+            Automaton::BriansBrain => 3,
+        }
+    }
+
+    /// Colors for each state, indexed by `Cell::state_index`
+    pub fn palette(self) -> Vec<[u8; 3]> {
+        match self {
+            Automaton::Wireworld => vec![[0, 0, 0], [32, 96, 255], [255, 64, 32], [255, 200, 0]],
+            Automaton::BriansBrain => vec![[0, 0, 0], [255, 255, 255], [32, 64, 200]],
+        }
+    }
+
+    /// Advances `last` by a generation into `next`, which must be the same size
+    pub fn step(self, last: &Board, topology: Topology, next: &mut Board) {
+        match self {
+            Automaton::Wireworld => {
+                for i in 0..last.cell_count() {
+                    next.cells[i] = match last.cells[i] {
+                        Cell::Alive => WIREWORLD_TAIL,
+                        WIREWORLD_TAIL => WIREWORLD_CONDUCTOR,
+                        WIREWORLD_CONDUCTOR => {
+                            let (x, y) = last.get_coord(i);
+                            let mut heads = 0;
+                            for dy in -1..=1 {
+                                for dx in -1..=1 {
+                                    if (dx != 0 || dy != 0)
+                                        && last.get(x + dx, y + dy, topology) == Some(Cell::Alive)
+                                    {
+                                        heads += 1;
+                                    }
+                                }
+                            }
+                            if heads == 1 || heads == 2 {
+                                Cell::Alive
+                            } else {
+                                WIREWORLD_CONDUCTOR
+                            }
+                        }
+                        _ => Cell::Dead,
+                    };
+                }
+            }
+            Automaton::BriansBrain => {
+                let rule = self.rule().unwrap();
+                for i in 0..last.cell_count() {
+                    next.cells[i] = crate::get_next_cell_state(last, &rule, topology, i);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Automaton {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // These match the rule names used by Golly
+        match self {
+            Automaton::Wireworld => write!(f, "WireWorld"),
+            Automaton::BriansBrain => write!(f, "BriansBrain"),
+        }
+    }
+}
+
+#[test]
+fn wireworld_electron_travels_along_a_wire() {
+    let mut board = Board {
+        width: 6,
+        height: 1,
+        cells: vec![WIREWORLD_CONDUCTOR; 6].into_boxed_slice(),
+    };
+    board.cells[0] = WIREWORLD_TAIL;
+    board.cells[1] = Cell::Alive;
+    let mut next = Board {
+        width: 6,
+        height: 1,
+        cells: board.cells.clone(),
+    };
+
+    Automaton::Wireworld.step(&board, Topology::Bounded, &mut next);
+    assert_eq!(
+        &*next.cells,
+        &[
+            WIREWORLD_CONDUCTOR,
+            WIREWORLD_TAIL,
+            Cell::Alive,
+            WIREWORLD_CONDUCTOR,
+            WIREWORLD_CONDUCTOR,
+            WIREWORLD_CONDUCTOR
+        ]
+    );
+    assert_eq!(
+        Automaton::parse("Brian's Brain"),
+        Ok(Automaton::BriansBrain)
+    );
+    assert_eq!(
+        Automaton::parse(&Automaton::Wireworld.to_string()),
+        Ok(Automaton::Wireworld)
+    );
+}
@@ -0,0 +1,321 @@
+//! Bit-packed board representation that simulates 64 cells at a time.  Each row of the board is
+//! stored as a run of `u64` words with bit `i` of word `k` holding the cell at `x = k * 64 + i`.
+//! Neighbor counts for a whole word are computed at once by summing the eight shifted neighbor
+//! words with a bitwise ripple-carry adder into four bit planes.
+
+use std::mem;
+
+use crate::{rule::Rule, topology::Topology, Board, Cell};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BitBoard {
+    pub width: usize,
+    pub height: usize,
+    pub words_per_row: usize,
+    pub words: Vec<u64>,
+}
+
+impl BitBoard {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = (width + 63) / 64;
+        BitBoard {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn from_board(board: &Board) -> Self {
+        let mut bits = BitBoard::new(board.width, board.height);
+        for (i, cell) in board.cells.iter().enumerate() {
+            if cell.is_alive() {
+                bits.set(i % board.width, i / board.width, true);
+            }
+        }
+        bits
+    }
+
+    pub fn to_board(&self) -> Board {
+        let cells = (0..self.width * self.height)
+            .map(|i| {
+                if self.get(i % self.width, i / self.width) {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        Board {
+            width: self.width,
+            height: self.height,
+            cells,
+        }
+    }
+
+    /// Returns the number of live cells
+    pub fn population(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let word = self.words[y * self.words_per_row + x / 64];
+        (word >> (x % 64)) & 1 != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        let word = &mut self.words[y * self.words_per_row + x / 64];
+        if alive {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+
+    #[inline]
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Mask of the bits in the last word of each row that correspond to cells on the board
+    #[inline]
+    fn last_word_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    /// Resolves the state of a single cell that may lie outside of the board
+    fn get_with_topology(&self, x: isize, y: isize, topology: Topology) -> bool {
+        match topology.map_coord(x, y, self.width, self.height) {
+            Some((x, y)) => self.get(x, y),
+            None => topology == Topology::AliveEdges,
+        }
+    }
+
+    /// Loads row `y`, which may lie outside of the board, into `out` (which has room for one
+    /// extra word) with the cell just past the right edge stored at bit `width`.  Returns the
+    /// state of the cell just past the left edge.
+    fn load_row(&self, y: isize, topology: Topology, out: &mut [u64]) -> u64 {
+        for word in out.iter_mut() {
+            *word = 0;
+        }
+
+        match topology.map_coord(0, y, self.width, self.height) {
+            None => {
+                if topology == Topology::AliveEdges {
+                    for word in out.iter_mut() {
+                        *word = !0;
+                    }
+                    return 1;
+                } else {
+                    return 0;
+                }
+            }
+            // The Klein bottle's twist maps the row's left end onto the right edge; the row has
+            // to be read in reverse.
+            Some((mapped_x, mapped_y)) if mapped_x != 0 => {
+                for x in 0..self.width {
+                    if self.get(self.width - 1 - x, mapped_y) {
+                        out[x / 64] |= 1 << (x % 64);
+                    }
+                }
+            }
+            Some((_, mapped_y)) => out[..self.words_per_row].copy_from_slice(self.row(mapped_y)),
+        }
+
+        if self.get_with_topology(self.width as isize, y, topology) {
+            out[self.width / 64] |= 1 << (self.width % 64);
+        }
+        self.get_with_topology(-1, y, topology) as u64
+    }
+
+    /// Computes the next generation of the board
+    pub fn step(&self, rule: &Rule, topology: Topology) -> BitBoard {
+        let mut next = BitBoard::new(self.width, self.height);
+        if self.width == 0 || self.height == 0 {
+            return next;
+        }
+
+        let word_count = self.words_per_row;
+        let last_word_mask = self.last_word_mask();
+        let mut above = vec![0u64; word_count + 1];
+        let mut center = vec![0u64; word_count + 1];
+        let mut below = vec![0u64; word_count + 1];
+        let mut left_above = self.load_row(-1, topology, &mut above);
+        let mut left_center = self.load_row(0, topology, &mut center);
+
+        for y in 0..self.height {
+            let left_below = self.load_row(y as isize + 1, topology, &mut below);
+
+            for k in 0..word_count {
+                let west = |row: &[u64], left: u64| {
+                    (row[k] << 1) | if k == 0 { left } else { row[k - 1] >> 63 }
+                };
+                let east = |row: &[u64]| (row[k] >> 1) | (row[k + 1] << 63);
+
+                let neighbors = [
+                    west(&above, left_above),
+                    above[k],
+                    east(&above),
+                    west(&center, left_center),
+                    east(&center),
+                    west(&below, left_below),
+                    below[k],
+                    east(&below),
+                ];
+
+                // Sum the neighbor words into four bit planes holding counts of 0 through 8
+                let mut planes = [0u64; 4];
+                for &neighbor in &neighbors {
+                    let mut carry = neighbor;
+                    for plane in planes.iter_mut() {
+                        let sum = *plane ^ carry;
+                        carry &= *plane;
+                        *plane = sum;
+                    }
+                }
+
+                let alive = center[k];
+                let mut next_word = 0u64;
+                for count in 0..9 {
+                    let (born, survives) = (rule.birth[count], rule.survival[count]);
+                    if !born && !survives {
+                        continue;
+                    }
+
+                    let mut count_matches = !0u64;
+                    for (bit, plane) in planes.iter().enumerate() {
+                        count_matches &= if (count >> bit) & 1 != 0 {
+                            *plane
+                        } else {
+                            !*plane
+                        };
+                    }
+
+                    if born {
+                        next_word |= count_matches & !alive;
+                    }
+                    if survives {
+                        next_word |= count_matches & alive;
+                    }
+                }
+
+                if k == word_count - 1 {
+                    next_word &= last_word_mask;
+                }
+                next.words[y * word_count + k] = next_word;
+            }
+
+            mem::swap(&mut above, &mut center);
+            mem::swap(&mut center, &mut below);
+            left_above = left_center;
+            left_center = left_below;
+        }
+
+        next
+    }
+
+    /// Calls `f` with the board index and new state of every cell that differs between `self`
+    /// and `other`
+    pub fn for_each_diff<F: FnMut(usize, Cell)>(&self, other: &BitBoard, mut f: F) {
+        for y in 0..self.height {
+            for k in 0..self.words_per_row {
+                let word_ix = y * self.words_per_row + k;
+                let mut diff = self.words[word_ix] ^ other.words[word_ix];
+                while diff != 0 {
+                    let bit = diff.trailing_zeros() as usize;
+                    diff &= diff - 1;
+
+                    let x = k * 64 + bit;
+                    let cell = if (other.words[word_ix] >> bit) & 1 != 0 {
+                        Cell::Alive
+                    } else {
+                        Cell::Dead
+                    };
+                    f(y * self.width + x, cell);
+                }
+            }
+        }
+    }
+}
+
+/// Builds a board from a simple LCG so that tests don't depend on `Math.random()`
+#[cfg(test)]
+pub(crate) fn test_board(width: usize, height: usize, seed: u64) -> Board {
+    let mut rng_state = seed;
+    let cells = (0..width * height)
+        .map(|_| {
+            rng_state = rng_state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            if (rng_state >> 33) % 3 == 0 {
+                Cell::Alive
+            } else {
+                Cell::Dead
+            }
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice();
+    Board {
+        width,
+        height,
+        cells,
+    }
+}
+
+#[test]
+fn bitboard_matches_reference_engine() {
+    let rules = [
+        "B3/S23",
+        "B36/S23",
+        "B2/S",
+        "B1357/S1357",
+        "B0123478/S34678",
+    ];
+    let topologies = [
+        Topology::Bounded,
+        Topology::Torus,
+        Topology::KleinBottle,
+        Topology::Mirror,
+        Topology::AliveEdges,
+    ];
+    let sizes = [(1, 1), (7, 5), (64, 9), (70, 33), (130, 4)];
+
+    for (seed, &(width, height)) in sizes.iter().enumerate() {
+        for rulestring in &rules {
+            let rule = Rule::parse(rulestring).unwrap();
+            for &topology in &topologies {
+                let mut board = test_board(width, height, seed as u64);
+                let mut bits = BitBoard::from_board(&board);
+
+                for generation in 0..8 {
+                    let cells = (0..board.cell_count())
+                        .map(|i| crate::get_next_cell_state(&board, &rule, topology, i))
+                        .collect::<Vec<_>>()
+                        .into_boxed_slice();
+                    board.cells = cells;
+                    bits = bits.step(&rule, topology);
+
+                    assert_eq!(
+                        bits,
+                        BitBoard::from_board(&board),
+                        "{} {:?} {}x{} diverged at generation {}",
+                        rulestring,
+                        topology,
+                        width,
+                        height,
+                        generation
+                    );
+                }
+            }
+        }
+    }
+}
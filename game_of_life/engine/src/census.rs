@@ -0,0 +1,572 @@
+//! Counts the objects on the board.  Live cells are split into components of cells that touch,
+//! including diagonally.  Under Conway's Life, groups of components within two cells of each other
+//! that make up a known object whose pieces don't touch, like the pulsar, are kept whole.  Each
+//! object is simulated in isolation to classify it as a still life, oscillator, or spaceship, and
+//! identified by a code that is the same under rotation, reflection, and across its phases.
+//!
+//! Codes follow apgsearch's prefixes: `xs<population>` for still lifes, `xp<period>` for
+//! oscillators, and `xq<period>` for spaceships, followed by the smallest RLE body of the object
+//! over all of its phases and orientations.  Components that don't repeat within
+//! `MAX_SIMULATED_GENERATIONS` (including those that are still evolving or that die out) are
+//! prefixed with `xx<population>` and identified by their current phase.
+
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+};
+
+use crate::{
+    patterns::{plaintext, Pattern},
+    rule::Rule,
+    topology::Topology,
+    Board,
+};
+
+/// Components are simulated for at most this many generations while looking for their period
+const MAX_SIMULATED_GENERATIONS: usize = 256;
+/// Components that grow beyond this many cells while being simulated are given up on
+const MAX_SIMULATED_POPULATION: usize = 4096;
+
+/// Names of common Conway's Life objects, given as plaintext in any phase and orientation
+const KNOWN_LIFE_OBJECTS: &[(&str, &str)] = &[
+    ("block", "OO\nOO"),
+    ("beehive", ".OO.\nO..O\n.OO."),
+    ("loaf", ".OO.\nO..O\n.O.O\n..O."),
+    ("boat", "OO.\nO.O\n.O."),
+    ("ship", "OO.\nO.O\n.OO"),
+    ("tub", ".O.\nO.O\n.O."),
+    ("pond", ".OO.\nO..O\nO..O\n.OO."),
+    ("long boat", "OO..\nO.O.\n.O.O\n..O."),
+    ("blinker", "OOO"),
+    ("toad", ".OOO\nOOO."),
+    ("beacon", "OO..\nOO..\n..OO\n..OO"),
+    ("pulsar", "..OOO...OOO..\n.............\nO....O.O....O\nO....O.O....O\nO....O.O....O\n..OOO...OOO..\n.............\n..OOO...OOO..\nO....O.O....O\nO....O.O....O\nO....O.O....O\n.............\n..OOO...OOO.."),
+    ("glider", ".O.\n..O\nOOO"),
+    ("lightweight spaceship", ".O..O\nO....\nO...O\nOOOO."),
+    ("middleweight spaceship", "...O..\n.O...O\nO.....\nO....O\nOOOOO."),
+    ("heavyweight spaceship", "...OO..\n.O....O\nO......\nO.....O\nOOOOOO."),
+];
+
+/// Names of the objects in `KNOWN_LIFE_OBJECTS` made of pieces that don't touch, which would
+/// otherwise be counted as separate components
+const DISCONNECTED_LIFE_OBJECTS: &[&str] = &["pulsar"];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator {
+        period: usize,
+    },
+    /// Moves `dx` cells along one axis and `dy` cells along the other every `period` generations.
+    /// The direction depends on which way the ship is facing, so the displacement is normalized to
+    /// `dx >= dy >= 0` to report every orientation of a ship the same way.
+    Spaceship {
+        period: usize,
+        dx: isize,
+        dy: isize,
+    },
+    /// Didn't repeat while it was simulated
+    Unstable,
+}
+
+impl ObjectKind {
+    fn name(self) -> &'static str {
+        match self {
+            ObjectKind::StillLife => "still_life",
+            ObjectKind::Oscillator { .. } => "oscillator",
+            ObjectKind::Spaceship { .. } => "spaceship",
+            ObjectKind::Unstable => "unstable",
+        }
+    }
+}
+
+/// A kind of object along with the number of times it appears on the board
+#[derive(Clone, PartialEq, Debug)]
+pub struct CensusEntry {
+    pub code: String,
+    pub name: Option<&'static str>,
+    pub kind: ObjectKind,
+    /// Population of the phase of the object with the smallest code
+    pub population: usize,
+    pub count: usize,
+}
+
+type Coords = Vec<(isize, isize)>;
+
+/// Labels each live cell of `board` with the index of its component, where cells up to `distance`
+/// cells apart along both axes belong to the same component.  If `wrap` is set, components
+/// continue across the edges of the board, and their cells are given coordinates past the edges
+/// so that they stay in one piece.  Returns the labels along with the cells of each component,
+/// the first of which is always on the board.
+fn label_components(
+    board: &Board,
+    distance: isize,
+    wrap: bool,
+) -> (Vec<Option<usize>>, Vec<Coords>) {
+    let (width, height) = (board.width as isize, board.height as isize);
+    let mut labels = vec![None; board.cell_count()];
+    let mut components = Vec::new();
+    for start in 0..board.cell_count() {
+        if labels[start].is_some() || !board.cells[start].is_alive() {
+            continue;
+        }
+
+        let label = components.len();
+        labels[start] = Some(label);
+        let mut component = Vec::new();
+        let mut stack = vec![board.get_coord(start)];
+        while let Some((x, y)) = stack.pop() {
+            component.push((x, y));
+            for dy in -distance..=distance {
+                for dx in -distance..=distance {
+                    let (nx, ny) = (x + dx, y + dy);
+                    let (board_x, board_y) = if wrap {
+                        (nx.rem_euclid(width), ny.rem_euclid(height))
+                    } else if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                        continue;
+                    } else {
+                        (nx, ny)
+                    };
+                    let neighbor = (board_y * width + board_x) as usize;
+                    if labels[neighbor].is_none() && board.cells[neighbor].is_alive() {
+                        labels[neighbor] = Some(label);
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+        }
+        components.push(component);
+    }
+    (labels, components)
+}
+
+/// Splits the live cells of `board` into the objects to classify.  Objects are the components of
+/// cells that touch, except under Conway's Life, where components within two cells of each other
+/// that together make up one of `DISCONNECTED_LIFE_OBJECTS` are kept together.
+fn objects(board: &Board, is_life: bool, wrap: bool) -> Vec<Coords> {
+    let (_, mut pieces) = label_components(board, 1, wrap);
+    if !is_life {
+        return pieces;
+    }
+
+    let (cluster_labels, clusters) = label_components(board, 2, wrap);
+    let mut cluster_pieces = vec![Vec::new(); clusters.len()];
+    for (i, piece) in pieces.iter().enumerate() {
+        let (x, y) = piece[0];
+        let cluster = cluster_labels[y as usize * board.width + x as usize].unwrap();
+        cluster_pieces[cluster].push(i);
+    }
+
+    let mut objects = Vec::new();
+    for (cluster, piece_indices) in clusters.into_iter().zip(cluster_pieces) {
+        if piece_indices.len() > 1 && is_disconnected_life_object(&cluster) {
+            objects.push(cluster);
+        } else {
+            objects.extend(piece_indices.into_iter().map(|i| mem::take(&mut pieces[i])));
+        }
+    }
+    objects
+}
+
+/// Advances a set of live cells on an unbounded plane by a generation
+fn step(cells: &HashSet<(isize, isize)>, rule: &Rule) -> HashSet<(isize, isize)> {
+    let mut neighbor_counts: HashMap<(isize, isize), usize> = HashMap::new();
+    for &(x, y) in cells {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if dx != 0 || dy != 0 {
+                    *neighbor_counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    neighbor_counts
+        .into_iter()
+        .filter(|&(coord, count)| {
+            if cells.contains(&coord) {
+                rule.survival[count]
+            } else {
+                rule.birth[count]
+            }
+        })
+        .map(|(coord, _)| coord)
+        .collect()
+}
+
+/// Shifts `cells` so that the top-left of their bounding box is at the origin, returning the shift
+/// and the shifted cells in sorted order
+fn normalize(cells: &HashSet<(isize, isize)>) -> ((isize, isize), Coords) {
+    let min_x = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let mut shape: Coords = cells.iter().map(|&(x, y)| (x - min_x, y - min_y)).collect();
+    shape.sort();
+    ((min_x, min_y), shape)
+}
+
+/// Encodes a pattern as the body of an RLE without the trailing `!`
+fn rle_body(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    for y in 0..pattern.height {
+        if y > 0 {
+            out.push('$');
+        }
+        // Trailing dead cells are left off of each row
+        let row_len = (0..pattern.width)
+            .rev()
+            .find(|&x| pattern.get(x, y).is_alive())
+            .map(|x| x + 1)
+            .unwrap_or(0);
+        let mut x = 0;
+        while x < row_len {
+            let alive = pattern.get(x, y).is_alive();
+            let run_start = x;
+            while x < row_len && pattern.get(x, y).is_alive() == alive {
+                x += 1;
+            }
+            if x - run_start > 1 {
+                out.push_str(&(x - run_start).to_string());
+            }
+            out.push(if alive { 'o' } else { 'b' });
+        }
+    }
+    out
+}
+
+/// Returns the smallest RLE body of `shape` over all rotations and reflections.  Shorter bodies
+/// are smaller, and bodies of the same length are compared lexicographically.
+fn canonical_body(shape: &[(isize, isize)]) -> String {
+    let pattern = Pattern::from_coords(shape);
+    let mut bodies =
+        (0..8).map(|orientation| rle_body(&pattern.transformed(orientation % 4, orientation >= 4)));
+    let first = bodies.next().unwrap();
+    bodies.fold(first, |smallest, body| {
+        if (body.len(), &body) < (smallest.len(), &smallest) {
+            body
+        } else {
+            smallest
+        }
+    })
+}
+
+/// Simulates a component in isolation to find out what kind of object it is.  Returns the kind
+/// along with the smallest canonical body and the population of the phase that it came from.
+fn classify(component: &[(isize, isize)], rule: &Rule) -> (ObjectKind, String, usize) {
+    let mut cells: HashSet<(isize, isize)> = component.iter().cloned().collect();
+    let (start_offset, start_shape) = normalize(&cells);
+    let mut phases = vec![start_shape.clone()];
+
+    let mut kind = ObjectKind::Unstable;
+    for generation in 1..=MAX_SIMULATED_GENERATIONS {
+        cells = step(&cells, rule);
+        if cells.is_empty() || cells.len() > MAX_SIMULATED_POPULATION {
+            break;
+        }
+
+        let (offset, shape) = normalize(&cells);
+        if shape == start_shape {
+            let (dx, dy) = (offset.0 - start_offset.0, offset.1 - start_offset.1);
+            kind = match (generation, dx, dy) {
+                (1, 0, 0) => ObjectKind::StillLife,
+                (period, 0, 0) => ObjectKind::Oscillator { period },
+                (period, dx, dy) => ObjectKind::Spaceship {
+                    period,
+                    dx: dx.abs().max(dy.abs()),
+                    dy: dx.abs().min(dy.abs()),
+                },
+            };
+            break;
+        }
+        phases.push(shape);
+    }
+
+    // Objects that didn't repeat are identified by the phase they're in now
+    if kind == ObjectKind::Unstable {
+        phases.truncate(1);
+    }
+    phases
+        .iter()
+        .map(|shape| (canonical_body(shape), shape.len()))
+        .fold(
+            None,
+            |smallest: Option<(String, usize)>, (body, population)| match smallest {
+                Some((ref smallest_body, _))
+                    if (smallest_body.len(), smallest_body) <= (body.len(), &body) =>
+                {
+                    smallest
+                }
+                _ => Some((body, population)),
+            },
+        )
+        .map(|(body, population)| (kind, body, population))
+        .unwrap()
+}
+
+fn object_code(kind: ObjectKind, body: &str, population: usize) -> String {
+    match kind {
+        ObjectKind::StillLife => format!("xs{}_{}", population, body),
+        ObjectKind::Oscillator { period } => format!("xp{}_{}", period, body),
+        ObjectKind::Spaceship { period, .. } => format!("xq{}_{}", period, body),
+        ObjectKind::Unstable => format!("xx{}_{}", population, body),
+    }
+}
+
+thread_local! {
+    /// Codes of the objects in `KNOWN_LIFE_OBJECTS` along with their names, which are only worked
+    /// out once
+    static KNOWN_LIFE_CODES: HashMap<String, &'static str> = known_life_objects();
+    /// Codes of `DISCONNECTED_LIFE_OBJECTS` along with the populations of all of their phases
+    static DISCONNECTED_LIFE_CODES: (HashSet<String>, HashSet<usize>) =
+        disconnected_life_objects();
+}
+
+/// Returns the codes of the objects in `KNOWN_LIFE_OBJECTS` along with their names
+fn known_life_objects() -> HashMap<String, &'static str> {
+    let life = Rule::default();
+    KNOWN_LIFE_OBJECTS
+        .iter()
+        .map(|&(name, cells)| {
+            let pattern = plaintext::parse(cells).expect("Invalid known object");
+            let coords: Coords = pattern
+                .live_coords()
+                .into_iter()
+                .map(|(x, y)| (x as isize, y as isize))
+                .collect();
+            let (kind, body, population) = classify(&coords, &life);
+            (object_code(kind, &body, population), name)
+        })
+        .collect()
+}
+
+/// Returns the codes of `DISCONNECTED_LIFE_OBJECTS` along with the populations of their phases
+fn disconnected_life_objects() -> (HashSet<String>, HashSet<usize>) {
+    let life = Rule::default();
+    let mut codes = HashSet::new();
+    let mut populations = HashSet::new();
+    for &(name, cells) in KNOWN_LIFE_OBJECTS {
+        if !DISCONNECTED_LIFE_OBJECTS.contains(&name) {
+            continue;
+        }
+
+        let pattern = plaintext::parse(cells).expect("Invalid known object");
+        let mut cells: HashSet<(isize, isize)> = pattern
+            .live_coords()
+            .into_iter()
+            .map(|(x, y)| (x as isize, y as isize))
+            .collect();
+        let coords: Coords = cells.iter().cloned().collect();
+        let (kind, body, population) = classify(&coords, &life);
+        let period = match kind {
+            ObjectKind::Oscillator { period } => period,
+            _ => 1,
+        };
+        for _ in 0..period {
+            populations.insert(cells.len());
+            cells = step(&cells, &life);
+        }
+        codes.insert(object_code(kind, &body, population));
+    }
+    (codes, populations)
+}
+
+/// Returns `true` if `cells` make up one of `DISCONNECTED_LIFE_OBJECTS`
+fn is_disconnected_life_object(cells: &[(isize, isize)]) -> bool {
+    DISCONNECTED_LIFE_CODES.with(|(codes, populations)| {
+        // Only worth simulating if it has the right number of cells
+        if !populations.contains(&cells.len()) {
+            return false;
+        }
+        let (kind, body, population) = classify(cells, &Rule::default());
+        codes.contains(&object_code(kind, &body, population))
+    })
+}
+
+/// Takes a census of the objects on `board` under `rule`, which must be a two-state rule on the
+/// square grid without B0.  Objects that cross the edges of a torus are kept whole; every other
+/// topology is treated as bounded.  Entries are sorted from most to least common.
+pub fn take_census(board: &Board, rule: &Rule, topology: Topology) -> Vec<CensusEntry> {
+    let is_life = *rule == Rule::default();
+    let mut entries: Vec<CensusEntry> = Vec::new();
+    let mut entry_indices: HashMap<String, usize> = HashMap::new();
+    for object in objects(board, is_life, topology == Topology::Torus) {
+        let (kind, body, population) = classify(&object, rule);
+        let code = object_code(kind, &body, population);
+        match entry_indices.get(&code) {
+            Some(&i) => entries[i].count += 1,
+            None => {
+                entry_indices.insert(code.clone(), entries.len());
+                let name = if is_life {
+                    KNOWN_LIFE_CODES.with(|names| names.get(&code).cloned())
+                } else {
+                    None
+                };
+                entries.push(CensusEntry {
+                    name,
+                    code,
+                    kind,
+                    population,
+                    count: 1,
+                });
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.code.cmp(&b.code)));
+    entries
+}
+
+impl CensusEntry {
+    pub fn to_json(&self) -> String {
+        let (period, dx, dy) = match self.kind {
+            ObjectKind::StillLife => (1, 0, 0),
+            ObjectKind::Oscillator { period } => (period, 0, 0),
+            ObjectKind::Spaceship { period, dx, dy } => (period, dx, dy),
+            ObjectKind::Unstable => (0, 0, 0),
+        };
+        let name = match self.name {
+            Some(name) => format!("\"{}\"", name),
+            None => "null".to_owned(),
+        };
+        format!(
+            "{{\"code\":\"{}\",\"name\":{},\"kind\":\"{}\",\"period\":{},\"dx\":{},\"dy\":{},\"population\":{},\"count\":{}}}",
+            self.code,
+            name,
+            self.kind.name(),
+            period,
+            dx,
+            dy,
+            self.population,
+            self.count
+        )
+    }
+}
+
+/// Encodes a census as a JSON array of its entries
+pub fn census_to_json(entries: &[CensusEntry]) -> String {
+    let entries: Vec<String> = entries.iter().map(CensusEntry::to_json).collect();
+    format!("[{}]", entries.join(","))
+}
+
+#[test]
+fn census_classifies_objects() {
+    use crate::Cell;
+
+    let mut board = Board {
+        width: 40,
+        height: 40,
+        cells: vec![Cell::Dead; 1600].into_boxed_slice(),
+    };
+    let mut place = |pattern: &str, x: usize, y: usize| {
+        let pattern = plaintext::parse(pattern).unwrap();
+        for (px, py) in pattern.live_coords() {
+            board.cells[(y + py) * 40 + x + px] = Cell::Alive;
+        }
+    };
+    // Two blocks, a blinker in each phase, a glider facing a different direction than the one in
+    // the table of known objects, and a pulsar, whose pieces don't touch
+    place("OO\nOO", 1, 1);
+    place("OO\nOO", 15, 1);
+    place("OOO", 6, 1);
+    place("O\nO\nO", 12, 6);
+    place("OOO\nO..\n.O.", 2, 12);
+    let (_, pulsar) = KNOWN_LIFE_OBJECTS
+        .iter()
+        .find(|&&(name, _)| name == "pulsar")
+        .unwrap();
+    place(pulsar, 22, 22);
+
+    let census = take_census(&board, &Rule::default(), Topology::Bounded);
+    let summary: Vec<(Option<&str>, ObjectKind, usize)> = census
+        .iter()
+        .map(|entry| (entry.name, entry.kind, entry.count))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some("blinker"), ObjectKind::Oscillator { period: 2 }, 2),
+            (Some("block"), ObjectKind::StillLife, 2),
+            (Some("pulsar"), ObjectKind::Oscillator { period: 3 }, 1),
+            (
+                Some("glider"),
+                ObjectKind::Spaceship {
+                    period: 4,
+                    dx: 1,
+                    dy: 1
+                },
+                1
+            ),
+        ]
+    );
+    assert_eq!(census[1].code, "xs4_2o$2o");
+    assert!(census_to_json(&census).starts_with("[{\"code\":\"xp2_3o\",\"name\":\"blinker\""));
+}
+
+#[test]
+fn census_separates_nearby_objects() {
+    use crate::Cell;
+
+    let mut board = Board {
+        width: 40,
+        height: 40,
+        cells: vec![Cell::Dead; 1600].into_boxed_slice(),
+    };
+    // Two blocks and a beehive, each one cell away from the next, and a pulsar in its second
+    // phase, whose pieces are laid out differently than in the table of known objects
+    for &(x, y) in &[
+        (1, 1),
+        (2, 1),
+        (1, 2),
+        (2, 2),
+        (4, 1),
+        (5, 1),
+        (4, 2),
+        (5, 2),
+    ] {
+        board.cells[y * 40 + x] = Cell::Alive;
+    }
+    let beehive = plaintext::parse(".OO.\nO..O\n.OO.").unwrap();
+    for (x, y) in beehive.live_coords() {
+        board.cells[(y + 1) * 40 + x + 7] = Cell::Alive;
+    }
+    let (_, pulsar) = KNOWN_LIFE_OBJECTS
+        .iter()
+        .find(|&&(name, _)| name == "pulsar")
+        .unwrap();
+    let mut pulsar: HashSet<(isize, isize)> = plaintext::parse(pulsar)
+        .unwrap()
+        .live_coords()
+        .into_iter()
+        .map(|(x, y)| (x as isize, y as isize))
+        .collect();
+    pulsar = step(&pulsar, &Rule::default());
+    for (x, y) in pulsar {
+        board.cells[(y + 20) as usize * 40 + (x + 20) as usize] = Cell::Alive;
+    }
+
+    let census = take_census(&board, &Rule::default(), Topology::Bounded);
+    let summary: Vec<(Option<&str>, usize)> = census
+        .iter()
+        .map(|entry| (entry.name, entry.count))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some("block"), 2),
+            (Some("pulsar"), 1),
+            (Some("beehive"), 1)
+        ]
+    );
+
+    // A block split across the corners of a torus
+    let mut board = Board {
+        width: 10,
+        height: 10,
+        cells: vec![Cell::Dead; 100].into_boxed_slice(),
+    };
+    for &i in &[0, 9, 90, 99] {
+        board.cells[i] = Cell::Alive;
+    }
+    let census = take_census(&board, &Rule::default(), Topology::Torus);
+    assert_eq!(census.len(), 1);
+    assert_eq!((census[0].name, census[0].count), (Some("block"), 1));
+}
@@ -0,0 +1,174 @@
+//! Detects when the board has become static or periodic by hashing each generation and watching
+//! for a hash that has been seen before.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+};
+
+use wasm_bindgen::prelude::*;
+
+use crate::Cell;
+
+/// Hashes of at most this many recent generations are kept, which is also the longest period that
+/// can be detected
+const MAX_TRACKED_GENERATIONS: usize = 1 << 16;
+
+/// Returned to the JS once the board has started repeating itself
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CycleInfo {
+    start: u64,
+    period: u64,
+}
+
+#[wasm_bindgen]
+impl CycleInfo {
+    /// First generation of the cycle
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> f64 {
+        self.start as f64
+    }
+
+    /// Number of generations after which the board repeats; 1 for a static board
+    #[wasm_bindgen(getter)]
+    pub fn period(&self) -> f64 {
+        self.period as f64
+    }
+}
+
+fn hash_cells(cells: &[Cell], phase: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(phase);
+    for cell in cells {
+        hasher.write_u8(cell.state_index());
+    }
+    hasher.finish()
+}
+
+fn hash_words(words: &[u64], phase: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(phase);
+    words.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Default)]
+pub struct CycleDetector {
+    /// Generation at which each hash was last seen
+    seen: HashMap<u64, u64>,
+    /// Hashes in the order they were recorded so that the oldest can be forgotten
+    order: VecDeque<(u64, u64)>,
+    /// Set when the board is changed by something other than the simulation, which makes the
+    /// recorded hashes meaningless
+    stale: bool,
+    /// Whether the recorded hashes are of bit-packed words rather than cells.  The same board
+    /// hashes differently either way, so switching between them forgets everything recorded.
+    hashing_words: bool,
+    pub cycle: Option<CycleInfo>,
+}
+
+impl CycleDetector {
+    /// Forgets everything that has been recorded the next time a generation is recorded
+    #[inline]
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// Returns `true` if generations are being recorded and haven't been invalidated since
+    pub fn is_tracking(&self) -> bool {
+        !self.stale && !self.order.is_empty()
+    }
+
+    /// Records a generation by the board's cells.  Boards are only matched against earlier boards
+    /// with the same `phase`, which tells apart identical boards that evolve differently, like
+    /// those of block rules at generations that use different partitions.
+    pub fn record(&mut self, generation: u64, cells: &[Cell], phase: u64) {
+        self.record_hash(generation, hash_cells(cells, phase), false);
+    }
+
+    /// Records a generation by the words of a bit-packed board, which is much cheaper than
+    /// hashing its cells
+    pub fn record_words(&mut self, generation: u64, words: &[u64], phase: u64) {
+        self.record_hash(generation, hash_words(words, phase), true);
+    }
+
+    fn record_hash(&mut self, generation: u64, hash: u64, hashing_words: bool) {
+        if self.stale || self.hashing_words != hashing_words {
+            self.hashing_words = hashing_words;
+            self.seen.clear();
+            self.order.clear();
+            self.cycle = None;
+            self.stale = false;
+        }
+
+        if let Some(&first_generation) = self.seen.get(&hash) {
+            if self.cycle.is_none() && first_generation < generation {
+                self.cycle = Some(CycleInfo {
+                    start: first_generation,
+                    period: generation - first_generation,
+                });
+            }
+        } else {
+            self.seen.insert(hash, generation);
+        }
+
+        self.order.push_back((hash, generation));
+        if self.order.len() > MAX_TRACKED_GENERATIONS {
+            let (old_hash, old_generation) = self.order.pop_front().unwrap();
+            if self.seen.get(&old_hash) == Some(&old_generation) {
+                self.seen.remove(&old_hash);
+            }
+        }
+    }
+}
+
+#[test]
+fn blinker_has_period_two() {
+    use crate::{rule::Rule, topology::Topology, Board};
+
+    let mut board = Board {
+        width: 7,
+        height: 7,
+        cells: vec![Cell::Dead; 49].into_boxed_slice(),
+    };
+    // A blinker next to a pre-block that takes a generation to settle into a block
+    for &i in &[8, 9, 10, 39, 40, 46] {
+        board.cells[i] = Cell::Alive;
+    }
+
+    let mut detector = CycleDetector::default();
+    detector.record(0, &board.cells, 0);
+    for generation in 1..6 {
+        board.cells = (0..board.cell_count())
+            .map(|i| crate::get_next_cell_state(&board, &Rule::default(), Topology::Bounded, i))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        detector.record(generation, &board.cells, 0);
+    }
+    assert_eq!(
+        detector.cycle,
+        Some(CycleInfo {
+            start: 1,
+            period: 2
+        })
+    );
+
+    detector.invalidate();
+    assert!(!detector.is_tracking());
+    detector.record(10, &board.cells, 0);
+    assert_eq!(detector.cycle, None);
+
+    // Switching to hashing packed words forgets the hashes of cells
+    let words = [0b110, 0b110];
+    detector.record_words(11, &words, 0);
+    assert_eq!(detector.cycle, None);
+    detector.record_words(12, &words, 0);
+    assert_eq!(
+        detector.cycle,
+        Some(CycleInfo {
+            start: 11,
+            period: 1
+        })
+    );
+}
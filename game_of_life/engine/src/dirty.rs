@@ -0,0 +1,177 @@
+//! Tracks which parts of the canvas have been drawn to since it was last rendered so that the JS
+//! only needs to upload those parts of the pixel buffer.
+//!
+//! Changed cells are collected into a handful of bounding rectangles.  A cell close to an existing
+//! rectangle grows it; otherwise it starts a new one until `MAX_RECTS` exist, after which it grows
+//! whichever rectangle that would get the least bigger.
+
+/// Most rectangles tracked at once
+const MAX_RECTS: usize = 8;
+/// Cells within this many cells of a rectangle are merged into it rather than starting a new one
+const MERGE_DISTANCE: usize = 4;
+
+/// Inclusive range of cells `(min_x, min_y)` to `(max_x, max_y)`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DirtyRect {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl DirtyRect {
+    fn area(&self) -> usize {
+        (self.max_x - self.min_x + 1) * (self.max_y - self.min_y + 1)
+    }
+
+    fn expanded_to(&self, x: usize, y: usize) -> Self {
+        DirtyRect {
+            min_x: self.min_x.min(x),
+            min_y: self.min_y.min(y),
+            max_x: self.max_x.max(x),
+            max_y: self.max_y.max(y),
+        }
+    }
+
+    fn union(&self, other: &DirtyRect) -> Self {
+        self.expanded_to(other.min_x, other.min_y)
+            .expanded_to(other.max_x, other.max_y)
+    }
+
+    /// Returns `true` if `(x, y)` lies inside of this rectangle or within `distance` cells of it
+    fn is_near(&self, x: usize, y: usize, distance: usize) -> bool {
+        x + distance >= self.min_x
+            && x <= self.max_x + distance
+            && y + distance >= self.min_y
+            && y <= self.max_y + distance
+    }
+
+    fn overlaps(&self, other: &DirtyRect) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+}
+
+#[derive(Default)]
+pub struct DirtyRegion {
+    rects: Vec<DirtyRect>,
+}
+
+impl DirtyRegion {
+    /// Marks the cell at (`x`, `y`) as having been drawn to
+    #[inline]
+    pub fn add(&mut self, x: usize, y: usize) {
+        if let Some(rect) = self
+            .rects
+            .iter_mut()
+            .find(|rect| rect.is_near(x, y, MERGE_DISTANCE))
+        {
+            *rect = rect.expanded_to(x, y);
+            return;
+        }
+
+        if self.rects.len() < MAX_RECTS {
+            self.rects.push(DirtyRect {
+                min_x: x,
+                min_y: y,
+                max_x: x,
+                max_y: y,
+            });
+            return;
+        }
+
+        let rect = self
+            .rects
+            .iter_mut()
+            .min_by_key(|rect| rect.expanded_to(x, y).area() - rect.area())
+            .unwrap();
+        *rect = rect.expanded_to(x, y);
+    }
+
+    /// Marks everything within a `width` x `height` board as having been drawn to
+    pub fn add_all(&mut self, width: usize, height: usize) {
+        self.rects.clear();
+        if width > 0 && height > 0 {
+            self.rects.push(DirtyRect {
+                min_x: 0,
+                min_y: 0,
+                max_x: width - 1,
+                max_y: height - 1,
+            });
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Returns the tracked rectangles with any overlapping ones merged, clearing the region
+    pub fn take(&mut self) -> Vec<DirtyRect> {
+        let mut rects = std::mem::replace(&mut self.rects, Vec::new());
+
+        // Rectangles that grew after being created can end up overlapping others, which would
+        // make the JS upload the same pixels twice
+        let mut merged_any = true;
+        while merged_any {
+            merged_any = false;
+            'outer: for i in 0..rects.len() {
+                for j in i + 1..rects.len() {
+                    if rects[i].overlaps(&rects[j]) {
+                        rects[i] = rects[i].union(&rects[j]);
+                        rects.swap_remove(j);
+                        merged_any = true;
+                        break 'outer;
+                    }
+                }
+            }
+        }
+        rects
+    }
+}
+
+#[test]
+fn nearby_cells_share_a_rect() {
+    let mut region = DirtyRegion::default();
+    region.add(10, 10);
+    region.add(12, 11);
+    region.add(50, 50);
+    assert_eq!(
+        region.take(),
+        vec![
+            DirtyRect {
+                min_x: 10,
+                min_y: 10,
+                max_x: 12,
+                max_y: 11
+            },
+            DirtyRect {
+                min_x: 50,
+                min_y: 50,
+                max_x: 50,
+                max_y: 50
+            },
+        ]
+    );
+    assert!(region.is_empty());
+
+    // Once the limit is reached, new cells grow the existing rects, which are merged if they
+    // end up overlapping
+    for i in 0..MAX_RECTS + 2 {
+        region.add(i * 20, i * 20);
+    }
+    region.add(0, 200);
+    let rects = region.take();
+    assert!(rects.len() <= MAX_RECTS);
+    for (i, a) in rects.iter().enumerate() {
+        for b in &rects[i + 1..] {
+            assert!(!a.overlaps(b));
+        }
+    }
+    for i in 0..MAX_RECTS + 2 {
+        assert!(rects.iter().any(|rect| rect.is_near(i * 20, i * 20, 0)));
+    }
+    assert!(rects.iter().any(|rect| rect.is_near(0, 200, 0)));
+}
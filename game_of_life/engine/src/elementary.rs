@@ -0,0 +1,214 @@
+//! One-dimensional cellular automata, drawn as a spacetime diagram in which each row of the board
+//! is a generation.  Supports Wolfram's elementary rules (`W30`, `W110`, ...) and totalistic rules
+//! with more states and a wider neighborhood (`T<code>,R<radius>,K<states>`).
+//!
+//! Under a totalistic rule, the new state of a cell is digit `s` (counting from the least
+//! significant) of the rule's code written in base `K`, where `s` is the sum of the states of the
+//! `2R + 1` cells centered on it.  `W<n>` is equivalent to an outer totalistic rule only for some
+//! `n`, so elementary rules are looked up directly from the three cells instead.
+
+use std::fmt;
+
+use crate::{topology::Topology, Cell};
+
+/// Widest neighborhood radius accepted for totalistic rules
+const MAX_RADIUS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Rule1d {
+    /// Wolfram elementary rule; bit `4l + 2c + r` is the new state of a cell whose left neighbor,
+    /// self, and right neighbor are `l`, `c`, and `r`
+    Elementary(u8),
+    Totalistic {
+        code: u64,
+        radius: usize,
+        states: u8,
+    },
+}
+
+/// Returns `true` if `rulestring` is written in one of the 1D notations, as opposed to a 2D one
+pub fn is_1d_rulestring(rulestring: &str) -> bool {
+    let rulestring = rulestring.trim();
+    let mut chars = rulestring.chars();
+    match chars.next() {
+        Some('W') | Some('w') | Some('T') | Some('t') => {
+            chars.next().map(|c| c.is_ascii_digit()).unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+impl Rule1d {
+    pub fn parse(rulestring: &str) -> Result<Self, String> {
+        let rulestring = rulestring.trim();
+        let invalid = || format!("Invalid 1D rulestring: \"{}\"", rulestring);
+
+        if rulestring.starts_with('W') || rulestring.starts_with('w') {
+            let number: u32 = rulestring[1..].parse().map_err(|_| invalid())?;
+            if number > 255 {
+                return Err(format!(
+                    "Elementary rules are numbered from 0 to 255, but got {}",
+                    number
+                ));
+            }
+            return Ok(Rule1d::Elementary(number as u8));
+        }
+
+        let (mut code, mut radius, mut states) = (None, 1, 2);
+        for field in rulestring.split(',') {
+            let field = field.trim();
+            if field.is_empty() {
+                return Err(invalid());
+            }
+            let (key, value) = field.split_at(1);
+            let value: u64 = value.parse().map_err(|_| invalid())?;
+            match key {
+                "T" | "t" => code = Some(value),
+                "R" | "r" => radius = value as usize,
+                "K" | "k" => states = value,
+                _ => return Err(invalid()),
+            }
+        }
+
+        let code = code.ok_or_else(invalid)?;
+        if !(1..=MAX_RADIUS).contains(&radius) {
+            return Err(format!(
+                "Totalistic rules must have a radius from 1 to {}",
+                MAX_RADIUS
+            ));
+        }
+        if !(2..=255).contains(&states) {
+            return Err("Totalistic rules must have from 2 to 255 states".to_owned());
+        }
+
+        // The code has a digit for every possible sum, so any higher digits are meaningless
+        let digits = (2 * radius as u32 + 1) * (states as u32 - 1) + 1;
+        if let Some(limit) = states.checked_pow(digits) {
+            if code >= limit {
+                return Err(format!(
+                    "Codes of totalistic rules with {} states and a radius of {} must be less \
+                     than {}",
+                    states, radius, limit
+                ));
+            }
+        }
+
+        Ok(Rule1d::Totalistic {
+            code,
+            radius,
+            states: states as u8,
+        })
+    }
+
+    pub fn states(self) -> u8 {
+        match self {
+            Rule1d::Elementary(_) => 2,
+            Rule1d::Totalistic { states, .. } => states,
+        }
+    }
+
+    fn radius(self) -> usize {
+        match self {
+            Rule1d::Elementary(_) => 1,
+            Rule1d::Totalistic { radius, .. } => radius,
+        }
+    }
+
+    /// Computes the generation after `row` into `next`, which must be the same length.  Only the
+    /// horizontal behavior of `topology` applies since there are no rows above or below.
+    pub fn step(self, row: &[Cell], topology: Topology, next: &mut [Cell]) {
+        let width = row.len();
+        let state_at = |x: isize| -> u64 {
+            match topology.map_coord(x, 0, width, 1) {
+                Some((x, _)) => row[x].state_index() as u64,
+                None if topology == Topology::AliveEdges => 1,
+                None => 0,
+            }
+        };
+
+        let radius = self.radius() as isize;
+        for x in 0..width as isize {
+            let new_state = match self {
+                Rule1d::Elementary(number) => {
+                    // Any state other than dead counts as alive
+                    let bit = |x: isize| (state_at(x) != 0) as u8;
+                    let neighborhood = (bit(x - 1) << 2) | (bit(x) << 1) | bit(x + 1);
+                    (number >> neighborhood) & 1
+                }
+                Rule1d::Totalistic { code, states, .. } => {
+                    let sum: u64 = (x - radius..=x + radius).map(state_at).sum();
+                    // Sums too large for their digit to fit in the code have a digit of zero
+                    match (states as u64).checked_pow(sum as u32) {
+                        Some(place) => ((code / place) % states as u64) as u8,
+                        None => 0,
+                    }
+                }
+            };
+            next[x as usize] = Cell::from_state_index(new_state);
+        }
+    }
+}
+
+impl fmt::Display for Rule1d {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule1d::Elementary(number) => write!(f, "W{}", number),
+            Rule1d::Totalistic {
+                code,
+                radius,
+                states,
+            } => write!(f, "T{},R{},K{}", code, radius, states),
+        }
+    }
+}
+
+/// State of the 1D mode, which draws generation `g` on row `g - seed_generation` of the board
+/// until the board fills up, after which the board scrolls up by a row every generation.  Seeding a
+/// row moves `seed_generation`, so board snapshots in the history carry it to be restored on undo.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Elementary {
+    pub rule: Rule1d,
+    /// Generation at which the first row was seeded
+    pub seed_generation: u64,
+}
+
+impl Elementary {
+    /// Returns the row of a board `height` cells tall that holds `generation`
+    pub fn row_of(&self, generation: u64, height: usize) -> usize {
+        (generation.saturating_sub(self.seed_generation) as usize).min(height - 1)
+    }
+}
+
+#[test]
+fn elementary_and_totalistic_rules() {
+    let to_row = |s: &str| -> Vec<Cell> {
+        s.chars()
+            .map(|c| Cell::from_state_index(c.to_digit(10).unwrap() as u8))
+            .collect()
+    };
+    let step = |rule: &str, row: &str, topology: Topology| -> Vec<Cell> {
+        let row = to_row(row);
+        let mut next = vec![Cell::Dead; row.len()];
+        Rule1d::parse(rule).unwrap().step(&row, topology, &mut next);
+        next
+    };
+
+    // Rule 30 grows a single cell into three, and rule 90 into two
+    assert_eq!(step("W30", "0001000", Topology::Bounded), to_row("0011100"));
+    assert_eq!(step("W90", "0001000", Topology::Bounded), to_row("0010100"));
+    // Wrapping brings the right edge's neighbor in from the left
+    assert_eq!(step("W30", "0000001", Topology::Bounded), to_row("0000011"));
+    assert_eq!(step("W30", "0000001", Topology::Torus), to_row("1000011"));
+    // Code 6 = 0b110: cells become alive with a neighborhood sum of 1 or 2
+    assert_eq!(step("T6", "0011000", Topology::Bounded), to_row("0111100"));
+    // Three states with a digit for each sum from 0 to 6: 2 for a sum of 1, 1 otherwise
+    assert_eq!(
+        step("T1096,K3", "0001000", Topology::Bounded),
+        to_row("1122211")
+    );
+
+    assert_eq!(Rule1d::parse("T6,R1,K2").unwrap().to_string(), "T6,R1,K2");
+    assert!(Rule1d::parse("W256").is_err());
+    assert!(Rule1d::parse("T256,R1,K2").is_err());
+    assert!(is_1d_rulestring("W110") && !is_1d_rulestring("B3/S23"));
+}
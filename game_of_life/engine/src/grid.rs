@@ -0,0 +1,188 @@
+//! Cell tilings that the board can be interpreted as.  All of them store cells in the same
+//! row-major array and differ only in which cells are neighbors and how cells are drawn.
+//!
+//! Hexagonal grids use the "odd-r" layout in which odd rows are shifted right by half a cell.
+//! Triangular grids alternate between upward and downward pointing triangles, with the cell at
+//! (x, y) pointing up when `x + y` is even.  Both wrap cleanly with the `Torus` topology only if
+//! the board's height (and for triangles, its width) is even.
+
+/// Largest number of neighbors that a cell can have on any grid
+pub const MAX_NEIGHBORS: usize = 12;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Grid {
+    /// Square cells with the 8-cell Moore neighborhood
+    Square,
+    /// Hexagonal cells with 6 neighbors
+    Hexagonal,
+    /// Triangular cells with the 12 neighbors that share an edge or vertex with them
+    Triangular,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid::Square
+    }
+}
+
+const SQUARE_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+const HEX_EVEN_ROW_OFFSETS: [(isize, isize); 6] =
+    [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+const HEX_ODD_ROW_OFFSETS: [(isize, isize); 6] =
+    [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+/// The apex of an upward triangle touches three cells of the row above, and its base touches five
+/// cells of the row below.  Downward triangles are the same, flipped vertically.
+const TRIANGLE_UP_OFFSETS: [(isize, isize); 12] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-2, 1),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (2, 1),
+];
+const TRIANGLE_DOWN_OFFSETS: [(isize, isize); 12] = [
+    (-2, -1),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (2, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+impl Grid {
+    pub fn neighbor_count(self) -> usize {
+        match self {
+            Grid::Square => 8,
+            Grid::Hexagonal => 6,
+            Grid::Triangular => 12,
+        }
+    }
+
+    /// Suffix that follows a rulestring to select this grid, as in `B2/S34H`
+    pub fn rule_suffix(self) -> &'static str {
+        match self {
+            Grid::Square => "",
+            Grid::Hexagonal => "H",
+            Grid::Triangular => "L",
+        }
+    }
+
+    /// Returns the offsets from the cell at (`x`, `y`) to each of its neighbors
+    #[inline]
+    pub fn neighbor_offsets(self, x: isize, y: isize) -> &'static [(isize, isize)] {
+        match self {
+            Grid::Square => &SQUARE_OFFSETS,
+            Grid::Hexagonal if y.rem_euclid(2) == 0 => &HEX_EVEN_ROW_OFFSETS,
+            Grid::Hexagonal => &HEX_ODD_ROW_OFFSETS,
+            Grid::Triangular if (x + y).rem_euclid(2) == 0 => &TRIANGLE_UP_OFFSETS,
+            Grid::Triangular => &TRIANGLE_DOWN_OFFSETS,
+        }
+    }
+
+    /// Returns the coordinate of the cell drawn at the canvas pixel (`px`, `py`) when each cell
+    /// takes up roughly `scale` x `scale` pixels, or `None` if no cell covers that pixel.
+    pub fn cell_at_pixel(
+        self,
+        px: usize,
+        py: usize,
+        scale: usize,
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let scale = scale as f32;
+        // Work in units of cells, sampling the center of the pixel
+        let (u, v) = ((px as f32 + 0.5) / scale, (py as f32 + 0.5) / scale);
+        let (x, y) = match self {
+            Grid::Square => (u.floor() as isize, v.floor() as isize),
+            Grid::Hexagonal => {
+                // Each pixel belongs to the hex whose center is closest, which carves the shifted
+                // rows into hexagons.
+                let row = v.floor() as isize;
+                let mut closest = (0, 0);
+                let mut closest_distance = std::f32::INFINITY;
+                for y in row - 1..=row + 1 {
+                    let row_offset = if y.rem_euclid(2) == 1 { 0.5 } else { 0. };
+                    let x_guess = (u - row_offset).floor() as isize;
+                    for x in x_guess - 1..=x_guess + 1 {
+                        let center_x = x as f32 + row_offset + 0.5;
+                        let center_y = y as f32 + 0.5;
+                        let distance = (u - center_x).powi(2) + (v - center_y).powi(2);
+                        if distance < closest_distance {
+                            closest = (x, y);
+                            closest_distance = distance;
+                        }
+                    }
+                }
+                closest
+            }
+            Grid::Triangular => {
+                // Triangles are twice as wide as a cell and overlap their neighbors by half.  The
+                // edge between columns k - 1 and k runs diagonally across [k - 0.5, k + 0.5].
+                let y = v.floor() as isize;
+                let row_v = v - y as f32;
+                let k = (u + 0.5).floor() as isize;
+                let edge_u = if (k + y).rem_euclid(2) == 0 {
+                    k as f32 + 0.5 - row_v
+                } else {
+                    k as f32 - 0.5 + row_v
+                };
+                (if u >= edge_u { k } else { k - 1 }, y)
+            }
+        };
+
+        if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn grid_neighborhoods_are_symmetric() {
+    // If `b` is a neighbor of `a`, then `a` must be a neighbor of `b`
+    for &grid in &[Grid::Square, Grid::Hexagonal, Grid::Triangular] {
+        for y in 0..4isize {
+            for x in 0..4isize {
+                let offsets = grid.neighbor_offsets(x, y);
+                assert_eq!(offsets.len(), grid.neighbor_count());
+                for &(dx, dy) in offsets {
+                    let reverse = grid.neighbor_offsets(x + dx, y + dy);
+                    assert!(reverse.contains(&(-dx, -dy)), "{:?} ({}, {})", grid, x, y);
+                }
+            }
+        }
+    }
+
+    // Every pixel away from the edges of the canvas belongs to some cell
+    for &grid in &[Grid::Hexagonal, Grid::Triangular] {
+        for py in 6..40 {
+            for px in 6..34 {
+                assert!(grid.cell_at_pixel(px, py, 6, 8, 8).is_some());
+            }
+        }
+    }
+}
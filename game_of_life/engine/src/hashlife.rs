@@ -0,0 +1,504 @@
+//! HashLife: a hash-consed quadtree representation of an unbounded universe with memoized
+//! successors, allowing huge or long-lived patterns to be advanced by 2^n generations at once.
+//!
+//! Every node of level `l` represents a 2^l x 2^l square of cells; level 0 nodes are single
+//! cells.  Identical squares are stored only once, and the result of advancing a node is cached,
+//! so repetitive patterns cost almost nothing to simulate no matter how far apart in space or
+//! time their repetitions are.
+
+use std::collections::HashMap;
+
+use crate::rule::Rule;
+
+pub type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Once the node arena grows past this many nodes, unreachable nodes are collected
+const GC_THRESHOLD: usize = 1 << 21;
+/// Steps larger than 2^MAX_STEP_EXPONENT generations are clamped so coordinates fit in an `i64`
+pub const MAX_STEP_EXPONENT: u32 = 48;
+
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    level: u8,
+    /// `[nw, ne, sw, se]`; unused for level 0 nodes
+    children: [NodeId; 4],
+    population: u64,
+}
+
+pub struct HashLife {
+    rule: Rule,
+    nodes: Vec<Node>,
+    node_ids: HashMap<[NodeId; 4], NodeId>,
+    /// Cache of `successor` results keyed by node and step exponent
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// Empty node for each level, created lazily
+    empty_nodes: Vec<NodeId>,
+    /// The root is always centered on the origin, covering `-2^(level - 1)..2^(level - 1)` on
+    /// both axes.
+    root: NodeId,
+    pub generation: u64,
+}
+
+impl HashLife {
+    pub fn new(rule: Rule) -> Self {
+        let leaf = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+        let mut universe = HashLife {
+            rule,
+            nodes: vec![leaf(0), leaf(1)],
+            node_ids: HashMap::new(),
+            results: HashMap::new(),
+            empty_nodes: vec![DEAD],
+            root: DEAD,
+            generation: 0,
+        };
+        universe.root = universe.empty(3);
+        universe
+    }
+
+    /// Builds a universe containing the live cells produced by `live_cells`
+    pub fn from_cells<I: IntoIterator<Item = (i64, i64)>>(rule: Rule, live_cells: I) -> Self {
+        let mut universe = HashLife::new(rule);
+        for (x, y) in live_cells {
+            universe.set(x, y, true);
+        }
+        universe
+    }
+
+    /// Swaps in a new rule, discarding all cached results computed under the old one
+    pub fn set_rule(&mut self, rule: Rule) {
+        if rule != self.rule {
+            self.rule = rule;
+            self.results.clear();
+        }
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    #[inline]
+    fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].level
+    }
+
+    #[inline]
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    #[inline]
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    /// Returns the canonical node with the given children, creating it if it doesn't exist yet
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.node_ids.get(&children) {
+            return id;
+        }
+
+        let level = self.level(children[0]) + 1;
+        let population = children
+            .iter()
+            .map(|&child| self.nodes[child as usize].population)
+            .sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            level,
+            children,
+            population,
+        });
+        self.node_ids.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty_nodes.len() <= level as usize {
+            let child = *self.empty_nodes.last().unwrap();
+            let empty = self.join([child; 4]);
+            self.empty_nodes.push(empty);
+        }
+        self.empty_nodes[level as usize]
+    }
+
+    /// Wraps `id` in a node one level higher, keeping it centered
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        let empty = self.empty(self.level(id) - 1);
+        let nw = self.join([empty, empty, empty, nw]);
+        let ne = self.join([empty, empty, ne, empty]);
+        let sw = self.join([empty, sw, empty, empty]);
+        let se = self.join([se, empty, empty, empty]);
+        self.join([nw, ne, sw, se])
+    }
+
+    /// Returns the node one level lower that covers the center of `id`
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.join([
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        ])
+    }
+
+    /// Returns the node of the same level as `west` and `east` that straddles the edge they
+    /// share
+    fn horizontal_center(&mut self, west: NodeId, east: NodeId) -> NodeId {
+        let [_, west_ne, _, west_se] = self.children(west);
+        let [east_nw, _, east_sw, _] = self.children(east);
+        self.join([west_ne, east_nw, west_se, east_sw])
+    }
+
+    /// Returns the node of the same level as `north` and `south` that straddles the edge they
+    /// share
+    fn vertical_center(&mut self, north: NodeId, south: NodeId) -> NodeId {
+        let [_, _, north_sw, north_se] = self.children(north);
+        let [south_nw, south_ne, _, _] = self.children(south);
+        self.join([north_sw, north_se, south_nw, south_ne])
+    }
+
+    /// Directly simulates one generation of the center 2x2 cells of a level 2 node
+    fn step_leaf_block(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (quadrant, &child) in self.children(id).iter().enumerate() {
+            for (sub_quadrant, &leaf) in self.children(child).iter().enumerate() {
+                let x = (quadrant % 2) * 2 + sub_quadrant % 2;
+                let y = (quadrant / 2) * 2 + sub_quadrant / 2;
+                cells[y][x] = leaf == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, next_cell) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + i % 2, 1 + i / 2);
+            let mut live_neighbor_count = 0;
+            for neighbor_y in y - 1..=y + 1 {
+                for neighbor_x in x - 1..=x + 1 {
+                    if (neighbor_x, neighbor_y) != (x, y) && cells[neighbor_y][neighbor_x] {
+                        live_neighbor_count += 1;
+                    }
+                }
+            }
+
+            let lives = if cells[y][x] {
+                self.rule.survival[live_neighbor_count]
+            } else {
+                self.rule.birth[live_neighbor_count]
+            };
+            *next_cell = if lives { ALIVE } else { DEAD };
+        }
+        self.join(next)
+    }
+
+    /// Returns the center of `id` (one level lower) advanced by 2^`step_exponent` generations.
+    /// `step_exponent` must be at most the node's level minus 2.
+    fn successor(&mut self, id: NodeId, step_exponent: u8) -> NodeId {
+        let level = self.level(id);
+        debug_assert!(level >= 2 && step_exponent <= level - 2);
+
+        if self.nodes[id as usize].population == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, step_exponent)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.step_leaf_block(id)
+        } else {
+            let [nw, ne, sw, se] = self.children(id);
+            let n01 = self.horizontal_center(nw, ne);
+            let n10 = self.vertical_center(nw, sw);
+            let n11 = self.center(id);
+            let n12 = self.vertical_center(ne, se);
+            let n21 = self.horizontal_center(sw, se);
+            let subnodes = [nw, n01, ne, n10, n11, n12, sw, n21, se];
+
+            // At full speed, both halves of the step advance time by 2^(level - 3) generations.
+            // Slower steps advance time only in the first half and just re-center in the second.
+            let full_speed = step_exponent == level - 2;
+            let first_exponent = if full_speed {
+                step_exponent - 1
+            } else {
+                step_exponent
+            };
+
+            let mut stepped = [DEAD; 9];
+            for (i, &subnode) in subnodes.iter().enumerate() {
+                stepped[i] = self.successor(subnode, first_exponent);
+            }
+
+            let mut quadrants = [DEAD; 4];
+            for (i, quadrant) in quadrants.iter_mut().enumerate() {
+                let (x, y) = (i % 2, i / 2);
+                let combined = self.join([
+                    stepped[y * 3 + x],
+                    stepped[y * 3 + x + 1],
+                    stepped[(y + 1) * 3 + x],
+                    stepped[(y + 1) * 3 + x + 1],
+                ]);
+                *quadrant = if full_speed {
+                    self.successor(combined, step_exponent - 1)
+                } else {
+                    self.center(combined)
+                };
+            }
+            self.join(quadrants)
+        };
+
+        self.results.insert((id, step_exponent), result);
+        result
+    }
+
+    /// Half the width of the root node
+    #[inline]
+    fn root_radius(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let radius = self.root_radius();
+        x >= -radius && x < radius && y >= -radius && y < radius
+    }
+
+    fn set_in_node(&mut self, id: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+
+        let half = 1i64 << (level - 1);
+        let quadrant = (if y >= half { 2 } else { 0 }) + (if x >= half { 1 } else { 0 });
+        let mut children = self.children(id);
+        children[quadrant] = self.set_in_node(children[quadrant], x % half, y % half, alive);
+        self.join(children)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        while !self.contains(x, y) {
+            self.root = self.expand(self.root);
+        }
+
+        let radius = self.root_radius();
+        self.root = self.set_in_node(self.root, x + radius, y + radius, alive);
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+
+        let radius = self.root_radius();
+        let (mut x, mut y) = (x + radius, y + radius);
+        let mut id = self.root;
+        while self.level(id) > 0 {
+            let half = 1i64 << (self.level(id) - 1);
+            let quadrant = (if y >= half { 2 } else { 0 }) + (if x >= half { 1 } else { 0 });
+            id = self.children(id)[quadrant];
+            x %= half;
+            y %= half;
+        }
+        id == ALIVE
+    }
+
+    /// Advances the universe by 2^`step_exponent` generations
+    pub fn step(&mut self, step_exponent: u32) {
+        let step_exponent = step_exponent.min(MAX_STEP_EXPONENT) as u8;
+
+        // Pad the root until the pattern can't possibly escape the result of the step, which
+        // covers the central half of the padded root.
+        while self.level(self.root) < step_exponent + 1 {
+            self.root = self.expand(self.root);
+        }
+        let padded = self.expand(self.root);
+        let padded = self.expand(padded);
+        self.root = self.successor(padded, step_exponent);
+        self.generation += 1 << step_exponent;
+
+        self.compact();
+        if self.nodes.len() > GC_THRESHOLD {
+            self.collect_garbage();
+        }
+    }
+
+    /// Strips empty padding from around the pattern
+    fn compact(&mut self) {
+        while self.level(self.root) > 3 {
+            let center = self.center(self.root);
+            if self.nodes[center as usize].population != self.population() {
+                break;
+            }
+            self.root = center;
+        }
+    }
+
+    fn copy_node(
+        &self,
+        id: NodeId,
+        new_universe: &mut HashLife,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(&new_id) = copied.get(&id) {
+            return new_id;
+        }
+
+        let mut children = self.children(id);
+        for child in children.iter_mut() {
+            *child = self.copy_node(*child, new_universe, copied);
+        }
+        let new_id = new_universe.join(children);
+        copied.insert(id, new_id);
+        new_id
+    }
+
+    /// Rebuilds the node arena with only the nodes reachable from the root, dropping all cached
+    /// results along with it.
+    fn collect_garbage(&mut self) {
+        let mut new_universe = HashLife::new(self.rule);
+        let mut copied = HashMap::new();
+        new_universe.root = self.copy_node(self.root, &mut new_universe, &mut copied);
+        new_universe.generation = self.generation;
+        *self = new_universe;
+    }
+
+    /// Calls `f` with the coordinates of every `2^zoom` x `2^zoom` block containing at least one
+    /// live cell within the area `cols` x `rows` blocks in size with its top-left corner at
+    /// (`x`, `y`).  Block coordinates are given relative to that corner, and (`x`, `y`) must be
+    /// multiples of `2^zoom`.
+    pub fn for_each_live_block<F: FnMut(usize, usize)>(
+        &mut self,
+        x: i64,
+        y: i64,
+        zoom: u32,
+        cols: usize,
+        rows: usize,
+        mut f: F,
+    ) {
+        let zoom = zoom.min(MAX_STEP_EXPONENT) as u8;
+        while self.level(self.root) < zoom + 1 {
+            self.root = self.expand(self.root);
+        }
+
+        let view = (
+            x,
+            y,
+            x + ((cols as i64) << zoom),
+            y + ((rows as i64) << zoom),
+        );
+        let radius = self.root_radius();
+        self.visit_live_blocks(self.root, -radius, -radius, zoom, view, &mut f);
+    }
+
+    fn visit_live_blocks<F: FnMut(usize, usize)>(
+        &self,
+        id: NodeId,
+        node_x: i64,
+        node_y: i64,
+        zoom: u8,
+        view: (i64, i64, i64, i64),
+        f: &mut F,
+    ) {
+        let node = self.nodes[id as usize];
+        let size = 1i64 << node.level;
+        let (view_x0, view_y0, view_x1, view_y1) = view;
+        if node.population == 0
+            || node_x + size <= view_x0
+            || node_y + size <= view_y0
+            || node_x >= view_x1
+            || node_y >= view_y1
+        {
+            return;
+        }
+
+        if node.level == zoom {
+            f(
+                ((node_x - view_x0) >> zoom) as usize,
+                ((node_y - view_y0) >> zoom) as usize,
+            );
+            return;
+        }
+
+        let half = size / 2;
+        for (quadrant, &child) in node.children.iter().enumerate() {
+            let child_x = node_x + if quadrant % 2 == 1 { half } else { 0 };
+            let child_y = node_y + if quadrant >= 2 { half } else { 0 };
+            self.visit_live_blocks(child, child_x, child_y, zoom, view, f);
+        }
+    }
+
+    fn collect_live_cells(
+        &self,
+        id: NodeId,
+        node_x: i64,
+        node_y: i64,
+        live_cells: &mut Vec<(i64, i64)>,
+    ) {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return;
+        } else if node.level == 0 {
+            live_cells.push((node_x, node_y));
+            return;
+        }
+
+        let half = 1i64 << (node.level - 1);
+        for (quadrant, &child) in node.children.iter().enumerate() {
+            let child_x = node_x + if quadrant % 2 == 1 { half } else { 0 };
+            let child_y = node_y + if quadrant >= 2 { half } else { 0 };
+            self.collect_live_cells(child, child_x, child_y, live_cells);
+        }
+    }
+
+    /// Returns the coordinates of every live cell in the universe
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut live_cells = Vec::with_capacity(self.population() as usize);
+        let radius = self.root_radius();
+        self.collect_live_cells(self.root, -radius, -radius, &mut live_cells);
+        live_cells
+    }
+}
+
+#[test]
+fn hashlife_matches_direct_simulation() {
+    use crate::{bitboard::BitBoard, topology::Topology};
+
+    // R-pentomino placed in the middle of a board big enough that it never touches the edges
+    // within the tested number of generations
+    let (width, height) = (256, 256);
+    let r_pentomino = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+    let rule = Rule::default();
+
+    let mut bits = BitBoard::new(width, height);
+    let mut universe = HashLife::new(rule);
+    for &(x, y) in &r_pentomino {
+        bits.set(x + 128, y + 128, true);
+        universe.set(x as i64 + 128, y as i64 + 128, true);
+    }
+
+    let mut generation = 0;
+    for &step_exponent in &[0, 0, 1, 2, 3, 4, 5] {
+        universe.step(step_exponent);
+        for _ in 0..1 << step_exponent {
+            bits = bits.step(&rule, Topology::Bounded);
+        }
+        generation += 1 << step_exponent;
+        assert_eq!(universe.generation, generation);
+
+        let mut from_universe = BitBoard::new(width, height);
+        universe.for_each_live_block(0, 0, 0, width, height, |x, y| from_universe.set(x, y, true));
+        assert_eq!(from_universe, bits, "diverged at generation {}", generation);
+        assert!(universe.get(128 + 1, 128 + 1) == bits.get(128 + 1, 128 + 1));
+    }
+}
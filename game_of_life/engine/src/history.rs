@@ -0,0 +1,271 @@
+//! Bounded undo history for the board.  A run-length compressed snapshot of the board is recorded
+//! before every generation along with each cell edited by the user, so both can be undone in the
+//! order that they happened.  While the bit-packed engine is running, snapshots are taken from
+//! its words rather than from the board's cells.  Generations of reversible rules don't need
+//! snapshots since they can be undone by running the rule backwards.
+
+use std::{collections::VecDeque, mem};
+
+use crate::{bitboard::BitBoard, margolus::BlockRule, topology::Topology, Cell};
+
+/// Once the recorded entries take up more than this many bytes, the oldest ones are dropped
+const MAX_HISTORY_BYTES: usize = 32 << 20;
+
+/// Appends `n` to `data` as a LEB128 varint
+fn push_varint(data: &mut Vec<u8>, mut n: usize) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            data.push(byte);
+            return;
+        }
+        data.push(byte | 0x80);
+    }
+}
+
+/// Reads a LEB128 varint from the front of `bytes`
+fn read_varint<'a>(bytes: &mut impl Iterator<Item = &'a u8>) -> usize {
+    let mut n = 0;
+    let mut shift = 0;
+    for &byte in bytes {
+        n |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    n
+}
+
+/// Board cells stored as runs of identical states.  Each run is the state's index followed by the
+/// length of the run as a LEB128 varint.  Boards that are already bit-packed are stored as runs of
+/// identical words instead, each of which is the word's little-endian bytes followed by the length
+/// of the run, which is much faster to build.
+pub struct CompressedBoard {
+    data: Vec<u8>,
+    /// Width and height of the board if `data` holds the words of a bit-packed board
+    packed_size: Option<(usize, usize)>,
+}
+
+impl CompressedBoard {
+    pub fn new(cells: &[Cell]) -> Self {
+        let mut data = Vec::new();
+        let mut i = 0;
+        while i < cells.len() {
+            let cell = cells[i];
+            let run_start = i;
+            while i < cells.len() && cells[i] == cell {
+                i += 1;
+            }
+
+            data.push(cell.state_index());
+            push_varint(&mut data, i - run_start);
+        }
+        CompressedBoard {
+            data,
+            packed_size: None,
+        }
+    }
+
+    /// Compresses a bit-packed board, which can only hold live and dead cells
+    pub fn from_packed(packed: &BitBoard) -> Self {
+        let words = &packed.words;
+        let mut data = Vec::new();
+        let mut i = 0;
+        while i < words.len() {
+            let word = words[i];
+            let run_start = i;
+            while i < words.len() && words[i] == word {
+                i += 1;
+            }
+
+            data.extend_from_slice(&word.to_le_bytes());
+            push_varint(&mut data, i - run_start);
+        }
+        CompressedBoard {
+            data,
+            packed_size: Some((packed.width, packed.height)),
+        }
+    }
+
+    pub fn decompress(&self) -> Vec<Cell> {
+        if let Some((width, height)) = self.packed_size {
+            let mut packed = BitBoard::new(width, height);
+            packed.words.clear();
+            let mut bytes = self.data.iter();
+            while bytes.len() > 0 {
+                let mut word_bytes = [0u8; 8];
+                for byte in word_bytes.iter_mut() {
+                    *byte = *bytes.next().unwrap();
+                }
+                let run_length = read_varint(&mut bytes);
+                let word = u64::from_le_bytes(word_bytes);
+                packed.words.extend((0..run_length).map(|_| word));
+            }
+            return packed.to_board().cells.into_vec();
+        }
+
+        let mut cells = Vec::new();
+        let mut bytes = self.data.iter();
+        while let Some(&state) = bytes.next() {
+            let run_length = read_varint(&mut bytes);
+            let cell = Cell::from_state_index(state);
+            cells.extend((0..run_length).map(|_| cell));
+        }
+        cells
+    }
+
+    #[inline]
+    pub fn size_bytes(&self) -> usize {
+        self.data.len()
+    }
+}
+
+pub enum HistoryEntry {
+    /// The board as it was at `generation`, recorded just before it was advanced or overwritten
+    Snapshot {
+        generation: u64,
+        cells: CompressedBoard,
+        /// Generation the active 1D rule was seeded at, since seeding it again moves the row
+        /// later generations are drawn on
+        seed_generation: Option<u64>,
+    },
+    /// A single cell edited by the user, along with its state before the edit
+    Edit { index: usize, before: Cell },
+    /// A generation of a reversible block rule, recorded just before the board was advanced from
+    /// `generation`
+    Reversible {
+        generation: u64,
+        rule: BlockRule,
+        topology: Topology,
+    },
+}
+
+impl HistoryEntry {
+    /// Returns the memory taken up by the entry, counting the entry itself so that long runs of
+    /// edits or reversible generations are bounded too
+    fn size_bytes(&self) -> usize {
+        let data_bytes = match self {
+            HistoryEntry::Snapshot { cells, .. } => cells.size_bytes(),
+            HistoryEntry::Edit { .. } | HistoryEntry::Reversible { .. } => 0,
+        };
+        mem::size_of::<Self>() + data_bytes
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    entries: VecDeque<HistoryEntry>,
+    size_bytes: usize,
+}
+
+impl History {
+    fn push(&mut self, entry: HistoryEntry) {
+        self.size_bytes += entry.size_bytes();
+        self.entries.push_back(entry);
+
+        while self.size_bytes > MAX_HISTORY_BYTES && self.entries.len() > 1 {
+            let dropped = self.entries.pop_front().unwrap();
+            self.size_bytes -= dropped.size_bytes();
+        }
+    }
+
+    pub fn push_snapshot(&mut self, generation: u64, cells: &[Cell], seed_generation: Option<u64>) {
+        self.push(HistoryEntry::Snapshot {
+            generation,
+            cells: CompressedBoard::new(cells),
+            seed_generation,
+        });
+    }
+
+    /// Records a snapshot of a board that's also held by the bit-packed engine, which is much
+    /// cheaper than compressing its cells
+    pub fn push_packed_snapshot(
+        &mut self,
+        generation: u64,
+        packed: &BitBoard,
+        seed_generation: Option<u64>,
+    ) {
+        self.push(HistoryEntry::Snapshot {
+            generation,
+            cells: CompressedBoard::from_packed(packed),
+            seed_generation,
+        });
+    }
+
+    pub fn push_edit(&mut self, index: usize, before: Cell) {
+        self.push(HistoryEntry::Edit { index, before });
+    }
+
+    pub fn push_reversible(&mut self, generation: u64, rule: BlockRule, topology: Topology) {
+        self.push(HistoryEntry::Reversible {
+            generation,
+            rule,
+            topology,
+        });
+    }
+
+    /// Removes and returns the most recent entry
+    pub fn pop(&mut self) -> Option<HistoryEntry> {
+        let entry = self.entries.pop_back()?;
+        self.size_bytes -= entry.size_bytes();
+        Some(entry)
+    }
+
+    /// Returns the earliest generation that can still be restored, if any
+    pub fn oldest_generation(&self) -> Option<u64> {
+        self.entries.iter().find_map(|entry| match entry {
+            HistoryEntry::Snapshot { generation, .. }
+            | HistoryEntry::Reversible { generation, .. } => Some(*generation),
+            HistoryEntry::Edit { .. } => None,
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.size_bytes = 0;
+    }
+}
+
+#[test]
+fn snapshots_round_trip() {
+    let mut cells = vec![Cell::Dead; 1000];
+    cells[3] = Cell::Alive;
+    cells[4] = Cell::Alive;
+    cells[500] = Cell::Dying(7);
+    let compressed = CompressedBoard::new(&cells);
+    assert!(compressed.size_bytes() < 16);
+    assert_eq!(compressed.decompress(), cells);
+
+    let board = crate::bitboard::test_board(130, 7, 3);
+    let packed = BitBoard::from_board(&board);
+    assert_eq!(
+        CompressedBoard::from_packed(&packed).decompress(),
+        board.cells.into_vec()
+    );
+    let empty = BitBoard::new(1000, 1000);
+    assert!(CompressedBoard::from_packed(&empty).size_bytes() < 16);
+
+    let mut history = History::default();
+    history.push_snapshot(5, &cells, None);
+    history.push_edit(3, Cell::Dead);
+    history.push_snapshot(6, &cells, None);
+    assert_eq!(history.oldest_generation(), Some(5));
+    match history.pop() {
+        Some(HistoryEntry::Snapshot { generation: 6, .. }) => (),
+        _ => panic!("Expected the most recent snapshot"),
+    }
+    match history.pop() {
+        Some(HistoryEntry::Edit { index: 3, .. }) => (),
+        _ => panic!("Expected the edit"),
+    }
+
+    // Edits alone are enough to evict the oldest entries
+    let max_edits = MAX_HISTORY_BYTES / mem::size_of::<HistoryEntry>();
+    for i in 0..max_edits + 100 {
+        history.push_edit(i, Cell::Dead);
+    }
+    assert_eq!(history.entries.len(), max_edits);
+    assert_eq!(history.oldest_generation(), None);
+}
@@ -10,111 +10,470 @@ use std::ptr;
 
 use wasm_bindgen::prelude::*;
 
+pub mod rule;
+use self::rule::Rule;
+pub mod topology;
+use self::topology::Topology;
+pub mod grid;
+use self::grid::Grid;
+pub mod patterns;
+use self::patterns::{life106, plaintext, rle, BlendMode, Pattern, PatternError};
+pub mod bitboard;
+use self::bitboard::BitBoard;
+pub mod ltl;
+use self::ltl::LtlRule;
+pub mod automaton;
+use self::automaton::Automaton;
+pub mod elementary;
+use self::elementary::{Elementary, Rule1d};
+pub mod margolus;
+use self::margolus::BlockRule;
+pub mod hashlife;
+use self::hashlife::HashLife;
+pub mod sparse;
+pub mod universe;
+use self::universe::Universe;
+pub mod viewport;
+use self::viewport::Viewport;
+pub mod history;
+use self::history::{History, HistoryEntry};
+pub mod cycle;
+use self::cycle::{CycleDetector, CycleInfo};
+pub mod stats;
+use self::stats::{GenerationStats, StatsSeries, Transitions};
+pub mod render;
+use self::render::{Gradient, RenderMode, Renderer};
+pub mod dirty;
+use self::dirty::DirtyRegion;
+pub mod selection;
+use self::selection::{Selection, SELECTION_OUTLINE_COLOR};
+pub mod census;
+pub mod search;
+use self::search::SearchParams;
+pub mod soup;
+
 #[wasm_bindgen(raw_module = "./index")]
 extern "C" {
-    pub fn canvasRender(ptr: *const u8);
+    /// `dirty_rects` holds `[x, y, width, height]` of each area of the canvas, in pixels, that
+    /// has changed since the last render.  Everything outside of them is unchanged.
+    pub fn canvasRender(ptr: *const u8, dirty_rects: &[u32]);
 }
 
-const BOARD_HEIGHT: usize = 150;
-const BOARD_WIDTH: usize = 150;
-const CELL_COUNT: usize = BOARD_HEIGHT * BOARD_WIDTH;
-const CANVAS_SCALE_FACTOR: usize = 6;
-const CANVAS_SIZE: usize = CELL_COUNT * 4 * CANVAS_SCALE_FACTOR * CANVAS_SCALE_FACTOR;
-
-#[derive(Clone, Copy, PartialEq)]
-enum Cell {
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cell {
     Dead,
     Alive,
+    /// Refractory state of a Generations rule, numbered from 2 up to the rule's state count - 1.
+    /// Dying cells don't count as live neighbors.
+    Dying(u8),
 }
 
 impl Cell {
-    fn is_alive(&self) -> bool {
+    pub fn is_alive(&self) -> bool {
         *self == Cell::Alive
     }
+
+    /// Returns the number of the state, with 0 for dead and 1 for alive
+    #[inline]
+    pub fn state_index(&self) -> u8 {
+        match *self {
+            Cell::Dead => 0,
+            Cell::Alive => 1,
+            Cell::Dying(state) => state,
+        }
+    }
+
+    #[inline]
+    pub fn from_state_index(state: u8) -> Self {
+        match state {
+            0 => Cell::Dead,
+            1 => Cell::Alive,
+            state => Cell::Dying(state),
+        }
+    }
 }
 
-struct Board(pub Box<[Cell; CELL_COUNT]>);
+pub struct Board {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Box<[Cell]>,
+}
 
 impl Board {
-    pub fn new() -> Self {
-        let mut cells = [Cell::Dead; CELL_COUNT];
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut cells = vec![Cell::Dead; width * height].into_boxed_slice();
         // Initialize cells randomly
-        for i in 0..CELL_COUNT {
-            if common::math_random() > 0.5 {
-                cells[i] = Cell::Alive;
-            }
+        for cell in cells.iter_mut() {
+            *cell = soup::random_cell(soup::DEFAULT_DENSITY);
+        }
+        Board {
+            width,
+            height,
+            cells,
         }
-        Board(box cells)
     }
 
-    pub fn get(&self, x: isize, y: isize) -> Option<Cell> {
-        if x < 0 || y < 0 || x >= BOARD_WIDTH as isize || y >= BOARD_HEIGHT as isize {
-            return None;
-        }
+    #[inline]
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
 
-        let index = (y * BOARD_WIDTH as isize) + x;
-        Some(self.0[index as usize])
+    #[inline]
+    pub fn get_coord(&self, index: usize) -> (isize, isize) {
+        let x = index % self.width;
+        let y = (index - x) / self.width;
+        return (x as isize, y as isize);
     }
-}
 
-struct State {
-    pub cur_buf_1: bool,
-    pub buf1: Board,
-    pub buf2: Board,
-    pub canvas_buf: Box<[u8; CANVAS_SIZE]>,
+    /// Returns the cell at the given coordinate.  Coordinates outside of the board are resolved
+    /// according to `topology`; `None` is returned for off-board coordinates that are dead.
+    pub fn get(&self, x: isize, y: isize, topology: Topology) -> Option<Cell> {
+        match topology.map_coord(x, y, self.width, self.height) {
+            Some((x, y)) => Some(self.cells[y * self.width + x]),
+            None if topology == Topology::AliveEdges => Some(Cell::Alive),
+            None => None,
+        }
+    }
 }
 
-#[inline]
-fn get_coord(index: usize) -> (isize, isize) {
-    let x = index % BOARD_WIDTH;
-    let y = (index - x) / BOARD_WIDTH;
-    return (x as isize, y as isize);
+/// Colors of dead and alive cells until they're changed with `set_state_color`
+const DEFAULT_PALETTE: [[u8; 3]; 2] = [[0, 0, 0], [255, 255, 255]];
+
+/// Most generations that `seek` will simulate in one call, since the page is frozen until it's
+/// done
+const MAX_SEEK_GENERATIONS: u64 = 100_000;
+
+/// RGBA pixel buffer that gets handed off to the JS for rendering.  Each cell of the board is
+/// drawn as a `scale_factor` x `scale_factor` square of pixels, or as a hexagon or triangle of
+/// about the same size on the other grids.
+struct Canvas {
+    pub board_width: usize,
+    pub board_height: usize,
+    pub scale_factor: usize,
+    pub buf: Box<[u8]>,
+    /// RGB color for each cell state, indexed by `Cell::state_index`
+    pub palette: Vec<[u8; 3]>,
+    pub grid: Grid,
+    /// Indices of the pixels that make up each cell on grids other than the square grid.  The
+    /// pixels of cell `i` are `cell_pixels[cell_pixel_starts[i]..cell_pixel_starts[i + 1]]`.
+    cell_pixel_starts: Vec<u32>,
+    cell_pixels: Vec<u32>,
+    /// Cells that have been drawn since the last render
+    dirty: DirtyRegion,
+    /// Pixels that are drawn over with the selection outline while rendering
+    overlay_pixels: Vec<usize>,
+    /// While set, drawing cells does nothing and whoever set it is responsible for redrawing the
+    /// cells that changed once it's cleared
+    pub suspended: bool,
 }
 
-impl State {
-    pub fn new() -> Self {
-        let mut canvas_buf = box [0u8; CANVAS_SIZE];
+impl Canvas {
+    pub fn new(board_width: usize, board_height: usize, scale_factor: usize) -> Self {
+        let size = board_width * board_height * 4 * scale_factor * scale_factor;
+        let mut buf = vec![0u8; size].into_boxed_slice();
 
         // Set transparency to 1 for all pixels
-        for i in 0..CANVAS_SIZE {
+        for i in 0..size {
             if i % 4 == 3 {
-                canvas_buf[i] = 255;
+                buf[i] = 255;
             }
         }
 
-        // Draw initial canvas buf
-        let buf1 = Board::new();
-        for i in 0..CELL_COUNT {
-            if buf1.0[i] == Cell::Alive {
-                State::draw_canvas_cell(&mut canvas_buf, i, Cell::Alive);
+        let mut dirty = DirtyRegion::default();
+        dirty.add_all(board_width, board_height);
+
+        Canvas {
+            board_width,
+            board_height,
+            scale_factor,
+            buf,
+            palette: DEFAULT_PALETTE.to_vec(),
+            grid: Grid::Square,
+            cell_pixel_starts: Vec::new(),
+            cell_pixels: Vec::new(),
+            dirty,
+            overlay_pixels: Vec::new(),
+            suspended: false,
+        }
+    }
+
+    /// Switches the shape that cells are drawn as.  The whole canvas is cleared to the dead color,
+    /// so every cell needs to be redrawn afterwards.
+    pub fn set_grid(&mut self, grid: Grid) {
+        self.grid = grid;
+        self.cell_pixel_starts.clear();
+        self.cell_pixels.clear();
+
+        let [r, g, b] = self.palette[0];
+        for pixel in self.buf.chunks_mut(4) {
+            pixel.copy_from_slice(&[r, g, b, 255]);
+        }
+        self.dirty.add_all(self.board_width, self.board_height);
+        if grid == Grid::Square {
+            return;
+        }
+
+        // Bucket every pixel by the cell that it belongs to
+        let canvas_width = self.board_width * self.scale_factor;
+        let canvas_height = self.board_height * self.scale_factor;
+        let mut pixel_cells = Vec::with_capacity(canvas_width * canvas_height);
+        let mut pixel_counts = vec![0u32; self.board_width * self.board_height];
+        for py in 0..canvas_height {
+            for px in 0..canvas_width {
+                let cell = grid
+                    .cell_at_pixel(
+                        px,
+                        py,
+                        self.scale_factor,
+                        self.board_width,
+                        self.board_height,
+                    )
+                    .map(|(x, y)| y * self.board_width + x);
+                if let Some(i) = cell {
+                    pixel_counts[i] += 1;
+                }
+                pixel_cells.push(cell);
             }
         }
 
-        State {
-            cur_buf_1: true,
-            buf1,
-            buf2: Board::new(),
-            canvas_buf: canvas_buf,
+        let mut start = 0;
+        self.cell_pixel_starts.push(0);
+        for count in pixel_counts {
+            start += count;
+            self.cell_pixel_starts.push(start);
+        }
+        let mut next_slot: Vec<u32> = self.cell_pixel_starts.clone();
+        self.cell_pixels = vec![0; start as usize];
+        for (pixel, cell) in pixel_cells.into_iter().enumerate() {
+            if let Some(i) = cell {
+                self.cell_pixels[next_slot[i] as usize] = pixel as u32;
+                next_slot[i] += 1;
+            }
+        }
+    }
+
+    /// Resizes the palette to hold a color for each of `states` states.  The colors of the dying
+    /// states are reset to a gradient that fades from the alive color to the dead color.
+    pub fn set_state_count(&mut self, states: u8) {
+        let (dead, alive) = (self.palette[0], self.palette[1]);
+        self.palette.truncate(2);
+        for state in 2..states as u32 {
+            let alive_weight = states as u32 - state;
+            let mut color = [0u8; 3];
+            for channel in 0..3 {
+                color[channel] = ((alive[channel] as u32 * alive_weight
+                    + dead[channel] as u32 * (state - 1))
+                    / (states as u32 - 1)) as u8;
+            }
+            self.palette.push(color);
         }
     }
 
-    pub fn draw_canvas_cell(canvas_buf: &mut [u8; CANVAS_SIZE], i: usize, state: Cell) {
-        let (x, y) = get_coord(i);
-        let write_val: u8 = if state == Cell::Alive { 255 } else { 0 };
+    /// Returns the color of `state` from the palette
+    #[inline]
+    pub fn palette_color(&self, state: Cell) -> [u8; 3] {
+        // States left over from a rule with more states than the current one are drawn as dead
+        self.palette
+            .get(state.state_index() as usize)
+            .cloned()
+            .unwrap_or(self.palette[0])
+    }
+
+    #[inline]
+    pub fn draw_cell(&mut self, i: usize, state: Cell) {
+        let color = self.palette_color(state);
+        self.draw_cell_color(i, color);
+    }
+
+    pub fn draw_cell_color(&mut self, i: usize, [r, g, b]: [u8; 3]) {
+        if self.suspended {
+            return;
+        }
+
+        let x = i % self.board_width;
+        let y = (i - x) / self.board_width;
+        self.dirty.add(x, y);
 
-        let px_per_row = BOARD_WIDTH * CANVAS_SCALE_FACTOR * 4;
-        let px_per_cell_row = px_per_row * CANVAS_SCALE_FACTOR;
+        if self.grid != Grid::Square {
+            let pixels = self.cell_pixel_starts[i] as usize..self.cell_pixel_starts[i + 1] as usize;
+            for &pixel in &self.cell_pixels[pixels] {
+                let array_ptr = unsafe { self.buf.as_ptr().offset(pixel as isize * 4) } as *mut u32;
+                unsafe { *array_ptr = mem::transmute((r, g, b, 255u8)) };
+            }
+            return;
+        }
 
-        let start_ix = (px_per_cell_row * y as usize) + (4 * x as usize * CANVAS_SCALE_FACTOR);
-        for row in 0..CANVAS_SCALE_FACTOR {
+        let px_per_row = self.board_width * self.scale_factor * 4;
+        let px_per_cell_row = px_per_row * self.scale_factor;
+
+        let start_ix = (px_per_cell_row * y) + (4 * x * self.scale_factor);
+        for row in 0..self.scale_factor {
             let cell_row_start_index = start_ix + (row * px_per_row);
-            for col in 0..CANVAS_SCALE_FACTOR {
+            for col in 0..self.scale_factor {
                 let cell_col_start_index = cell_row_start_index + (col * 4);
-                let array_ptr = unsafe { canvas_buf.as_ptr().offset(cell_col_start_index as isize) }
-                    as *mut u32;
-                unsafe { *array_ptr = mem::transmute((write_val, write_val, write_val, 255u8)) };
+                let array_ptr =
+                    unsafe { self.buf.as_ptr().offset(cell_col_start_index as isize) } as *mut u32;
+                unsafe { *array_ptr = mem::transmute((r, g, b, 255u8)) };
+            }
+        }
+    }
+
+    /// Moves everything drawn on the canvas up by `rows` rows of cells, leaving the bottom rows as
+    /// they were.  Only valid on the square grid.
+    pub fn scroll_up(&mut self, rows: usize) {
+        if self.suspended {
+            return;
+        }
+
+        let offset = rows * self.scale_factor * self.board_width * self.scale_factor * 4;
+        let len = self.buf.len();
+        if offset < len {
+            unsafe {
+                ptr::copy(
+                    self.buf.as_ptr().add(offset),
+                    self.buf.as_mut_ptr(),
+                    len - offset,
+                )
+            };
+        }
+        self.dirty.add_all(self.board_width, self.board_height);
+    }
+
+    /// Replaces the pixels drawn over with the selection outline.  The cells beneath both the old
+    /// and new outlines are marked dirty so that the outline is added or removed on the next
+    /// render.
+    pub fn set_overlay(&mut self, pixels: Vec<usize>) {
+        let canvas_width = self.board_width * self.scale_factor;
+        for &pixel in self.overlay_pixels.iter().chain(pixels.iter()) {
+            let (px, py) = (pixel % canvas_width, pixel / canvas_width);
+            self.dirty
+                .add(px / self.scale_factor, py / self.scale_factor);
+        }
+        self.overlay_pixels = pixels;
+    }
+
+    /// Converts the dirty cells into rectangles of pixels, flattened into `[x, y, width, height]`
+    /// quadruples
+    fn take_dirty_pixel_rects(&mut self) -> Vec<u32> {
+        // Hexagons and triangles spill over into the squares of the cells next to them
+        let padding = if self.grid == Grid::Square { 0 } else { 1 };
+        let scale = self.scale_factor;
+        let mut flat = Vec::new();
+        for rect in self.dirty.take() {
+            let min_x = rect.min_x.saturating_sub(padding);
+            let min_y = rect.min_y.saturating_sub(padding);
+            let max_x = (rect.max_x + padding).min(self.board_width - 1);
+            let max_y = (rect.max_y + padding).min(self.board_height - 1);
+            flat.extend_from_slice(&[
+                (min_x * scale) as u32,
+                (min_y * scale) as u32,
+                ((max_x - min_x + 1) * scale) as u32,
+                ((max_y - min_y + 1) * scale) as u32,
+            ]);
+        }
+        flat
+    }
+
+    /// Hands the pixel buffer off to the JS along with the areas of it that have changed since
+    /// the last render.  This is called even if nothing changed since the JS schedules the next
+    /// tick from it.
+    pub fn render(&mut self) {
+        let dirty_rects = self.take_dirty_pixel_rects();
+
+        // The outline is only drawn into the buffer for as long as the JS is reading it so that
+        // the cells beneath it don't need to be redrawn when it moves
+        let [r, g, b] = SELECTION_OUTLINE_COLOR;
+        let mut covered = Vec::with_capacity(self.overlay_pixels.len());
+        for &pixel in &self.overlay_pixels {
+            let rgba = &mut self.buf[pixel * 4..pixel * 4 + 4];
+            covered.push([rgba[0], rgba[1], rgba[2], rgba[3]]);
+            rgba.copy_from_slice(&[r, g, b, 255]);
+        }
+
+        canvasRender(self.buf.as_ptr(), &dirty_rects);
+
+        for (&pixel, rgba) in self.overlay_pixels.iter().zip(covered) {
+            self.buf[pixel * 4..pixel * 4 + 4].copy_from_slice(&rgba);
+        }
+    }
+}
+
+struct State {
+    pub cur_buf_1: bool,
+    pub buf1: Board,
+    pub buf2: Board,
+    pub canvas: Canvas,
+    pub rule: Rule,
+    /// Larger than Life rule that takes the place of `rule` when set
+    pub ltl_rule: Option<LtlRule>,
+    /// Automaton that can't be written as a rulestring, which takes the place of `rule` when set
+    pub automaton: Option<Automaton>,
+    /// Whether the palette holds the colors of an automaton, which are replaced with the default
+    /// colors once another rule takes over
+    pub automaton_palette: bool,
+    /// 1D rule that takes the place of `rule` when set, drawing each generation as a row
+    pub elementary: Option<Elementary>,
+    /// Reversible block rule on the Margolus neighborhood that takes the place of `rule` when set
+    pub margolus: Option<BlockRule>,
+    pub topology: Topology,
+    /// Whether `tick` uses the bit-packed engine or the per-cell reference engine
+    pub use_packed_engine: bool,
+    /// Bit-packed copy of the current buffer used by the packed engine.  It's kept in sync by
+    /// `set_cell_at_index` and is dropped whenever the reference engine runs.
+    pub packed: Option<BitBoard>,
+    /// Unbounded HashLife universe or sparse plane.  While one exists, `tick` advances it instead
+    /// of the board and the board mirrors the area of it visible through `viewport`.
+    pub universe: Option<Universe>,
+    pub viewport: Viewport,
+    /// Number of generations that the board has been advanced
+    pub generation: u64,
+    pub history: History,
+    pub cycles: CycleDetector,
+    /// Statistics for each recent generation of the board.  Generations of unbounded universes
+    /// aren't recorded.
+    pub stats: StatsSeries,
+    pub renderer: Renderer,
+    pub selection: Option<Selection>,
+    /// Cells copied or cut out of the board, including dead ones
+    pub clipboard: Option<Pattern>,
+}
+
+impl State {
+    pub fn new(width: usize, height: usize, scale_factor: usize) -> Self {
+        let mut canvas = Canvas::new(width, height, scale_factor);
+
+        // Draw initial canvas buf
+        let buf1 = Board::new(width, height);
+        for i in 0..buf1.cell_count() {
+            if buf1.cells[i] == Cell::Alive {
+                canvas.draw_cell(i, Cell::Alive);
             }
         }
+
+        State {
+            cur_buf_1: true,
+            buf1,
+            buf2: Board::new(width, height),
+            canvas,
+            rule: Rule::default(),
+            ltl_rule: None,
+            automaton: None,
+            automaton_palette: false,
+            elementary: None,
+            margolus: None,
+            topology: Topology::default(),
+            use_packed_engine: true,
+            packed: None,
+            universe: None,
+            viewport: Viewport::default(),
+            generation: 0,
+            history: History::default(),
+            cycles: CycleDetector::default(),
+            stats: StatsSeries::default(),
+            renderer: Renderer::default(),
+            selection: None,
+            clipboard: None,
+        }
     }
 
     pub fn get_cur_buf(&mut self) -> &mut Board {
@@ -124,6 +483,380 @@ impl State {
             &mut self.buf2
         }
     }
+
+    /// Sets the cell at index `i` of the current buffer and draws it to the canvas if it changed.
+    /// If an unbounded universe is active, the change is made to it as well as long as the
+    /// viewport isn't zoomed out.
+    pub fn set_cell_at_index(&mut self, i: usize, cell: Cell) {
+        if let Some(ref mut universe) = self.universe {
+            if self.viewport.zoom == 0 {
+                let (x, y) = self.buf1.get_coord(i);
+                let (x, y) = self.viewport.to_universe(x as usize, y as usize);
+                universe.set(x, y, cell.is_alive());
+            }
+        }
+
+        self.write_cell(i, cell);
+    }
+
+    /// Sets the cell at index `i` of the current buffer and draws it to the canvas if it changed
+    /// without touching the unbounded universe
+    pub fn write_cell(&mut self, i: usize, cell: Cell) {
+        let cur_buf = self.get_cur_buf();
+        if cur_buf.cells[i] == cell {
+            return;
+        }
+
+        cur_buf.cells[i] = cell;
+        let (x, y) = cur_buf.get_coord(i);
+        self.cycles.invalidate();
+        self.stats.invalidate();
+        if let Some(ref mut ages) = self.renderer.ages {
+            ages.reset_cell(i, cell);
+        }
+        self.draw_cell(i, cell);
+        if let Cell::Dying(_) = cell {
+            // The packed board can't hold dying cells, so it's rebuilt on the next tick
+            self.packed = None;
+        }
+        if let Some(ref mut packed) = self.packed {
+            packed.set(x as usize, y as usize, cell.is_alive());
+        }
+    }
+
+    /// Sets the cell at the given coordinate on the current buffer and draws it to the canvas if
+    /// it changed.  Coordinates outside of the board are ignored.
+    pub fn set_cell(&mut self, x: isize, y: isize, cell: Cell) {
+        let cur_buf = self.get_cur_buf();
+        if x < 0 || y < 0 || x >= cur_buf.width as isize || y >= cur_buf.height as isize {
+            return;
+        }
+
+        let i = y as usize * cur_buf.width + x as usize;
+        self.set_cell_at_index(i, cell);
+    }
+
+    /// Records the whole board in the history before it's overwritten without recording each
+    /// edit, so that undoing past the overwrite restores it.  Generations of reversible rules are
+    /// undone by running the rule backwards on whatever is on the board, so they'd scramble it if
+    /// the board they produced had been replaced.
+    pub fn snapshot_board(&mut self) {
+        let generation = self.generation;
+        let seed_generation = self.elementary.map(|elementary| elementary.seed_generation);
+        let cur_buf = if self.cur_buf_1 {
+            &self.buf1
+        } else {
+            &self.buf2
+        };
+        match self.packed {
+            Some(ref packed) => {
+                self.history
+                    .push_packed_snapshot(generation, packed, seed_generation)
+            }
+            None => self
+                .history
+                .push_snapshot(generation, &cur_buf.cells, seed_generation),
+        }
+    }
+
+    /// Records the current generation with the cycle detector, hashing the packed engine's words
+    /// rather than the board's cells while it's running
+    pub fn record_cycle(&mut self) {
+        let generation = self.generation;
+        // Block rules alternate between two partitions, so the same board only repeats itself if
+        // it comes back at a generation with the same parity
+        let phase = match self.margolus {
+            Some(_) => generation % 2,
+            None => 0,
+        };
+        match self.packed {
+            Some(ref packed) => self.cycles.record_words(generation, &packed.words, phase),
+            None => {
+                let cur_buf = if self.cur_buf_1 {
+                    &self.buf1
+                } else {
+                    &self.buf2
+                };
+                self.cycles.record(generation, &cur_buf.cells, phase);
+            }
+        }
+    }
+
+    /// Writes `pattern` onto the current buffer with its top-left corner at (`x`, `y`).  Any part
+    /// of the pattern that lies outside of the board is clipped.  The whole placement is undone
+    /// at once.
+    pub fn place_pattern(&mut self, pattern: &Pattern, x: isize, y: isize) {
+        self.snapshot_board();
+        for pattern_y in 0..pattern.height {
+            for pattern_x in 0..pattern.width {
+                self.set_cell(
+                    x + pattern_x as isize,
+                    y + pattern_y as isize,
+                    pattern.get(pattern_x, pattern_y),
+                );
+            }
+        }
+    }
+
+    /// Combines `pattern` with the current buffer according to `mode`, with its top-left corner at
+    /// (`x`, `y`).  Any part of the pattern that lies outside of the board is clipped.  The whole
+    /// stamp is undone at once.
+    pub fn stamp_pattern(&mut self, pattern: &Pattern, x: isize, y: isize, mode: BlendMode) {
+        self.snapshot_board();
+        let (width, height) = (self.buf1.width as isize, self.buf1.height as isize);
+        for pattern_y in 0..pattern.height {
+            for pattern_x in 0..pattern.width {
+                let (board_x, board_y) = (x + pattern_x as isize, y + pattern_y as isize);
+                if board_x < 0 || board_y < 0 || board_x >= width || board_y >= height {
+                    continue;
+                }
+
+                let i = (board_y * width + board_x) as usize;
+                let old_val = self.get_cur_buf().cells[i];
+                self.set_cell_at_index(i, mode.blend(old_val, pattern.get(pattern_x, pattern_y)));
+            }
+        }
+    }
+
+    /// Sets the cell at index `i` of the current buffer on behalf of the user, recording its
+    /// previous state in the undo history if it changed
+    pub fn edit_cell(&mut self, i: usize, cell: Cell) {
+        let old_val = self.get_cur_buf().cells[i];
+        if cell != old_val {
+            self.history.push_edit(i, old_val);
+            self.set_cell_at_index(i, cell);
+        }
+    }
+
+    /// Selects `selection`, or nothing if it's `None`, and updates the outline drawn around it
+    pub fn select(&mut self, selection: Option<Selection>) {
+        self.selection = selection;
+        let outline = match selection {
+            Some(selection) => selection.outline_pixels(
+                self.canvas.scale_factor,
+                self.canvas.board_width * self.canvas.scale_factor,
+            ),
+            None => Vec::new(),
+        };
+        self.canvas.set_overlay(outline);
+    }
+
+    /// Overwrites the board with the area of the unbounded universe visible through the viewport
+    pub fn render_viewport(&mut self) {
+        let viewport = self.viewport;
+        let (width, height) = (self.buf1.width, self.buf1.height);
+        let universe = match self.universe {
+            Some(ref mut universe) => universe,
+            None => return,
+        };
+
+        let mut visible = vec![Cell::Dead; width * height];
+        universe.for_each_live_block(
+            viewport.x,
+            viewport.y,
+            viewport.zoom,
+            width,
+            height,
+            |x, y| visible[y * width + x] = Cell::Alive,
+        );
+        for (i, cell) in visible.into_iter().enumerate() {
+            self.write_cell(i, cell);
+        }
+    }
+
+    /// Returns the number of states that cells can be in under the active rule
+    pub fn state_count(&self) -> u8 {
+        if let Some(elementary) = self.elementary {
+            return elementary.rule.states();
+        }
+        if self.margolus.is_some() {
+            return 2;
+        }
+        match (self.automaton, &self.ltl_rule) {
+            (Some(automaton), _) => automaton.states(),
+            (None, Some(ltl_rule)) => ltl_rule.states,
+            (None, None) => self.rule.states,
+        }
+    }
+
+    /// Returns the grid that the active rule runs on
+    pub fn grid(&self) -> Grid {
+        if self.elementary.is_some()
+            || self.margolus.is_some()
+            || self.automaton.is_some()
+            || self.ltl_rule.is_some()
+        {
+            Grid::Square
+        } else {
+            self.rule.grid
+        }
+    }
+
+    /// Updates the palette and cell shapes to match the active rule, if they changed.  The colors
+    /// of an automaton that's no longer active are swapped back for the default ones.
+    fn update_canvas(&mut self) {
+        let states = self.state_count();
+        let grid = self.grid();
+        let leaving_automaton = self.automaton_palette && self.automaton.is_none();
+        if !leaving_automaton
+            && self.canvas.palette.len() == states as usize
+            && self.canvas.grid == grid
+        {
+            return;
+        }
+
+        if leaving_automaton {
+            self.automaton_palette = false;
+            self.canvas.palette = DEFAULT_PALETTE.to_vec();
+        }
+        if self.canvas.palette.len() != states as usize {
+            self.canvas.set_state_count(states);
+        }
+        if self.canvas.grid != grid {
+            self.canvas.set_grid(grid);
+        }
+        self.redraw();
+    }
+
+    /// Switches to `rule`, replacing any Larger than Life rule, automaton, 1D rule, or block rule
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.ltl_rule = None;
+        self.automaton = None;
+        self.elementary = None;
+        self.margolus = None;
+        self.cycles.invalidate();
+        self.update_canvas();
+    }
+
+    pub fn set_ltl_rule(&mut self, ltl_rule: LtlRule) {
+        self.ltl_rule = Some(ltl_rule);
+        self.automaton = None;
+        self.elementary = None;
+        self.margolus = None;
+        self.cycles.invalidate();
+        self.update_canvas();
+    }
+
+    /// Switches to `automaton` and its colors.  Automata that are equivalent to a Generations
+    /// rule run as that rule.
+    pub fn set_automaton(&mut self, automaton: Automaton) {
+        match automaton.rule() {
+            Some(rule) => self.set_rule(rule),
+            None => {
+                self.ltl_rule = None;
+                self.automaton = Some(automaton);
+                self.elementary = None;
+                self.margolus = None;
+                self.cycles.invalidate();
+                self.update_canvas();
+            }
+        }
+        self.canvas.palette = automaton.palette();
+        self.automaton_palette = true;
+        self.redraw();
+    }
+
+    /// Switches to the 1D rule `rule`.  If a 1D rule was already active, the new one picks up from
+    /// the current row; otherwise the top row of the board becomes the first generation.
+    pub fn set_1d_rule(&mut self, rule: Rule1d) {
+        let seed_generation = match self.elementary {
+            Some(elementary) => elementary.seed_generation,
+            None => self.generation,
+        };
+        self.elementary = Some(Elementary {
+            rule,
+            seed_generation,
+        });
+        self.ltl_rule = None;
+        self.automaton = None;
+        self.margolus = None;
+        self.cycles.invalidate();
+        self.update_canvas();
+    }
+
+    /// Switches to the reversible block rule `rule`.  Block rules only have two states, so any
+    /// dying cells left over from a Generations rule are killed; otherwise they'd be read as dead
+    /// and undoing generations wouldn't bring them back.
+    pub fn set_block_rule(&mut self, rule: BlockRule) {
+        for i in 0..self.buf1.cell_count() {
+            if let Cell::Dying(_) = self.get_cur_buf().cells[i] {
+                self.edit_cell(i, Cell::Dead);
+            }
+        }
+        self.margolus = Some(rule);
+        self.ltl_rule = None;
+        self.automaton = None;
+        self.elementary = None;
+        self.cycles.invalidate();
+        self.update_canvas();
+    }
+
+    /// Parses and switches to a B/S rulestring, a Larger than Life rulestring, a 1D rulestring, a
+    /// Margolus rulestring, or the name of an automaton or Margolus rule
+    pub fn set_rulestring(&mut self, rulestring: &str) -> Result<(), String> {
+        if elementary::is_1d_rulestring(rulestring) {
+            self.set_1d_rule(Rule1d::parse(rulestring)?);
+        } else if margolus::is_margolus_rulestring(rulestring) {
+            self.set_block_rule(BlockRule::parse(rulestring)?);
+        } else if let Ok(automaton) = Automaton::parse(rulestring) {
+            self.set_automaton(automaton);
+        } else if ltl::is_ltl_rulestring(rulestring) {
+            self.set_ltl_rule(LtlRule::parse(rulestring)?);
+        } else {
+            self.set_rule(Rule::parse(rulestring)?);
+        }
+        Ok(())
+    }
+
+    /// Returns the active rule in its canonical notation
+    pub fn rulestring(&self) -> String {
+        if let Some(elementary) = self.elementary {
+            return elementary.rule.to_string();
+        }
+        if let Some(margolus) = self.margolus {
+            return margolus.to_string();
+        }
+        match (self.automaton, &self.ltl_rule) {
+            (Some(automaton), _) => automaton.to_string(),
+            (None, Some(ltl_rule)) => ltl_rule.to_string(),
+            (None, None) => self.rule.to_string(),
+        }
+    }
+
+    /// Switches to the rule embedded in a pattern file, if there is one that we understand
+    pub fn apply_pattern_rule(&mut self, pattern: &Pattern) {
+        let rulestring = match pattern.rule {
+            Some(ref rulestring) => rulestring,
+            None => return,
+        };
+
+        if let Err(err) = self.set_rulestring(rulestring) {
+            warn!("Ignoring rule from loaded pattern: {}", err);
+        }
+    }
+
+    /// Draws cell `i` to the canvas with the color picked by the active render mode
+    #[inline]
+    pub fn draw_cell(&mut self, i: usize, cell: Cell) {
+        let color = self
+            .renderer
+            .color(i, cell, self.canvas.palette_color(cell));
+        self.canvas.draw_cell_color(i, color);
+    }
+
+    /// Draws every cell of the current buffer to the canvas, which is needed after the palette
+    /// or render mode changes
+    pub fn redraw(&mut self) {
+        for i in 0..self.buf1.cell_count() {
+            let cell = if self.cur_buf_1 {
+                self.buf1.cells[i]
+            } else {
+                self.buf2.cells[i]
+            };
+            self.draw_cell(i, cell);
+        }
+    }
 }
 
 static mut STATE: *mut State = ptr::null_mut();
@@ -133,145 +866,1344 @@ fn state() -> &'static mut State {
     unsafe { mem::transmute(STATE) }
 }
 
-/// Called by the JS to initialize the game state before starting the simulation
+/// Called by the JS to initialize the game state before starting the simulation.  The board is
+/// `width` x `height` cells, and each cell is drawn as a `scale_factor` x `scale_factor` square
+/// of pixels on the canvas.
 #[wasm_bindgen]
-pub fn init() {
+pub fn init(width: usize, height: usize, scale_factor: usize) {
     let log_level = if cfg!(debug_assertions) {
         log::Level::Trace
     } else {
         log::Level::Info
     };
     wasm_logger::init(wasm_logger::Config::new(log_level));
-    let initial_state = box State::new();
+    // Every load starts from a different soup, which can be regenerated with `set_random_state`
+    let seed = (common::math_random() * u32::max_value() as f64) as u32;
+    info!("Initial soup seed: {}", seed);
+    soup::seed_rng(seed);
+    let initial_state = box State::new(width, height, scale_factor);
     let initial_state = Box::into_raw(initial_state);
     unsafe { STATE = initial_state as *mut State };
-    let state = state();
-    canvasRender(state.canvas_buf.as_ptr() as *const u8)
+    state().canvas.render();
 }
 
 #[wasm_bindgen]
 pub fn set_pixel(x: usize, y: usize) {
-    if x >= BOARD_WIDTH || y >= BOARD_HEIGHT {
+    let state = state();
+    let cur_buf = state.get_cur_buf();
+    if x >= cur_buf.width || y >= cur_buf.height {
         error!("({}, {}) is outside of the board", x, y);
         return;
     }
 
-    let state = state();
-    let cur_buf = state.get_cur_buf();
-
-    let i = y * BOARD_WIDTH + x;
-    let new_val = if cur_buf.0[i] == Cell::Alive {
+    let i = y * cur_buf.width + x;
+    let new_val = if cur_buf.cells[i] == Cell::Alive {
         Cell::Dead
     } else {
         Cell::Alive
     };
-    state.buf1.0[i] = new_val;
-    state.buf2.0[i] = new_val;
-    State::draw_canvas_cell(&mut state.canvas_buf, i, new_val);
-    canvasRender(state.canvas_buf.as_ptr() as *const u8);
+    state.edit_cell(i, new_val);
+    state.canvas.render();
+}
+
+/// Returns the index of the cell drawn at the given pixel of the canvas, or -1 if the pixel isn't
+/// covered by any cell.  Hexagonal and triangular cells don't line up with the square blocks of
+/// pixels that the JS would otherwise assume.
+#[wasm_bindgen]
+pub fn get_cell_at_canvas_pixel(x: usize, y: usize) -> isize {
+    let canvas = &state().canvas;
+    match canvas.grid.cell_at_pixel(
+        x,
+        y,
+        canvas.scale_factor,
+        canvas.board_width,
+        canvas.board_height,
+    ) {
+        Some((x, y)) => (y * canvas.board_width + x) as isize,
+        None => -1,
+    }
 }
 
 #[inline]
-fn get_next_cell_state(last_buf: &Board, index: usize) -> Cell {
-    let cur_state: Cell = last_buf.0[index];
-    let (x, y) = get_coord(index);
-
-    let neighbor_offets: [(isize, isize); 8] = [
-        (-1, -1),
-        (0, -1),
-        (1, -1),
-        (-1, 0),
-        (1, 0),
-        (-1, 1),
-        (0, 1),
-        (1, 1),
-    ];
+fn get_next_cell_state(last_buf: &Board, rule: &Rule, topology: Topology, index: usize) -> Cell {
+    let cur_state: Cell = last_buf.cells[index];
+    let (x, y) = last_buf.get_coord(index);
+
+    let neighbor_offets = rule.grid.neighbor_offsets(x, y);
     let live_neighbor_count = neighbor_offets
         .iter()
-        .map(|(x_offset, y_offset)| last_buf.get(x + *x_offset, y + *y_offset))
+        .map(|(x_offset, y_offset)| last_buf.get(x + *x_offset, y + *y_offset, topology))
         .filter(Option::is_some)
         .map(Option::unwrap)
         .filter(Cell::is_alive)
         .count();
 
-    if cur_state == Cell::Alive {
-        // Die if underpopulated
-        if live_neighbor_count < 2 {
-            return Cell::Dead;
-        }
-        // Live if 2 or 3 neighbors
-        else if live_neighbor_count == 2 || live_neighbor_count == 3 {
-            return Cell::Alive;
-        }
-        // Die if more than 3 neighbors
-        else {
-            return Cell::Dead;
-        }
+    match cur_state {
+        Cell::Alive if rule.survival[live_neighbor_count] => Cell::Alive,
+        Cell::Alive => rule.first_dying_state(),
+        Cell::Dead if rule.birth[live_neighbor_count] => Cell::Alive,
+        Cell::Dead => Cell::Dead,
+        Cell::Dying(state) => rule.next_dying_state(state),
     }
+}
 
-    // Spawn new cell if exactly three neighbors
-    if live_neighbor_count == 3 {
-        return Cell::Alive;
+/// Sets the rule used by `tick` from a rulestring such as `B3/S23`, `B36/S23`, a Generations rule
+/// like `B2/S/C3`, a Larger than Life rule like `R5,C0,M1,S34..58,B34..45,NM`, or a 1D rule like
+/// `W30` or `T1096,R1,K3` (see `seed_1d_single`), or a reversible Margolus block rule like
+/// `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15` or one of the names "critters", "tron", and "bbm".
+/// Returns `false` and leaves the current rule in place if the rulestring is invalid.
+#[wasm_bindgen]
+pub fn set_rule(rulestring: &str) -> bool {
+    match state().set_rulestring(rulestring) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("{}", err);
+            false
+        }
     }
-
-    // Stay dead as the base case
-    return Cell::Dead;
 }
 
+/// Switches to a named automaton along with its traditional colors.  Valid names are "wireworld"
+/// and "briansbrain".  Returns `false` if the name isn't recognized.
 #[wasm_bindgen]
-pub fn set_state(canvas_pattern: &[u8]) {
-    let state = state();
-
-    for (i, cell) in canvas_pattern.iter().enumerate() {
-        let cur_buf = state.get_cur_buf();
-        let cell_state = if *cell == 0 { Cell::Dead } else { Cell::Alive };
-        if cur_buf.0[i] != cell_state {
-            cur_buf.0[i] = cell_state;
-            State::draw_canvas_cell(&mut state.canvas_buf, i, cell_state);
+pub fn set_automaton(name: &str) -> bool {
+    let automaton = match Automaton::parse(name) {
+        Ok(automaton) => automaton,
+        Err(err) => {
+            error!("{}", err);
+            return false;
         }
-    }
+    };
 
-    canvasRender(state.canvas_buf.as_ptr() as *const u8);
+    let state = state();
+    state.set_automaton(automaton);
+    state.canvas.render();
+    true
 }
 
+/// Sets the cell at (`x`, `y`) to `cell_state`, or kills it if it's already in that state.  This
+/// is needed to draw states other than alive, like Wireworld's conductors.  Returns `false` if the
+/// coordinate is off of the board or the active rule has no such state.
 #[wasm_bindgen]
-pub fn set_random_state() {
+pub fn set_pixel_state(x: usize, y: usize, cell_state: u8) -> bool {
     let state = state();
+    let cur_buf = state.get_cur_buf();
+    if x >= cur_buf.width || y >= cur_buf.height {
+        error!("({}, {}) is outside of the board", x, y);
+        return false;
+    }
+    if cell_state >= state.state_count() {
+        error!(
+            "The active rule has no state {}; it only has {}",
+            cell_state,
+            state.state_count()
+        );
+        return false;
+    }
 
-    for i in 0..CELL_COUNT {
-        let cur_buf = state.get_cur_buf();
-        let new_state = if common::math_random() > 0.5 {
-            Cell::Alive
+    let i = y * state.buf1.width + x;
+    let cell = Cell::from_state_index(cell_state);
+    let new_val = if state.get_cur_buf().cells[i] == cell {
+        Cell::Dead
+    } else {
+        cell
+    };
+    state.edit_cell(i, new_val);
+    state.canvas.render();
+    true
+}
+
+/// Clears the board and writes `row` to the top row as the first generation of the active 1D rule,
+/// centered horizontally, then renders it.  Returns `false` if no 1D rule is active or `row` is
+/// wider than the board.
+fn seed_1d(state: &mut State, row: &[Cell]) -> bool {
+    if !write_1d_seed(state, row) {
+        return false;
+    }
+    state.canvas.render();
+    true
+}
+
+/// Does the work of `seed_1d` without rendering the board
+fn write_1d_seed(state: &mut State, row: &[Cell]) -> bool {
+    let width = state.buf1.width;
+    if state.elementary.is_none() {
+        error!("Rows can only be seeded while a 1D rule like `W30` is active");
+        return false;
+    }
+    if row.len() > width {
+        error!(
+            "The row is {} cells wide, but the board is only {}",
+            row.len(),
+            width
+        );
+        return false;
+    }
+
+    // The snapshot keeps the previous seed generation so that undoing the seed restores it
+    state.snapshot_board();
+    let generation = state.generation;
+    if let Some(ref mut elementary) = state.elementary {
+        elementary.seed_generation = generation;
+    }
+    let offset = (width - row.len()) / 2;
+    for i in 0..state.buf1.cell_count() {
+        let cell = if i >= offset && i < offset + row.len() {
+            row[i - offset]
         } else {
             Cell::Dead
         };
-        if cur_buf.0[i] != new_state {
-            cur_buf.0[i] = new_state;
-            State::draw_canvas_cell(&mut state.canvas_buf, i, new_state);
-        }
+        state.set_cell_at_index(i, cell);
     }
+    true
+}
 
-    canvasRender(state.canvas_buf.as_ptr() as *const u8);
+/// Starts the active 1D rule from a single live cell in the middle of the top row.  1D rules are
+/// set with `set_rule`; each generation is drawn as a row below the last, and the board scrolls up
+/// once it's full.  Returns `false` if no 1D rule is active.
+#[wasm_bindgen]
+pub fn seed_1d_single() -> bool {
+    seed_1d(state(), &[Cell::Alive])
 }
 
+/// Starts the active 1D rule from a random top row in which each cell is alive with probability
+/// `density`, in a state picked uniformly from the rule's live states.  The same `seed` and
+/// `density` always produce the same row.  Returns `false` if no 1D rule is active.
 #[wasm_bindgen]
-pub fn tick() {
+pub fn seed_1d_random(seed: u32, density: f64) -> bool {
     let state = state();
-    let (last_board, target_board): (&Board, &mut Board) = if state.cur_buf_1 {
-        (&state.buf1, &mut state.buf2)
-    } else {
-        (&state.buf2, &mut state.buf1)
-    };
+    let states = state.state_count();
+    soup::seed_rng(seed);
+    let row: Vec<Cell> = (0..state.buf1.width)
+        .map(|_| soup::random_state(density, states))
+        .collect();
+    seed_1d(state, &row)
+}
+
+/// Starts the active 1D rule from a custom top row given as the state of each cell, centered
+/// horizontally.  Returns `false` if no 1D rule is active, the row is wider than the board, or it
+/// contains states that the rule doesn't have.
+#[wasm_bindgen]
+pub fn seed_1d_custom(cells: &[u8]) -> bool {
+    let state = state();
+    let states = state.state_count();
+    if let Some(&cell_state) = cells.iter().find(|&&cell_state| cell_state >= states) {
+        error!(
+            "The active rule has no state {}; it only has {}",
+            cell_state, states
+        );
+        return false;
+    }
 
-    for i in 0..CELL_COUNT {
-        let new_val_for_cell = get_next_cell_state(last_board, i);
-        target_board.0[i] = new_val_for_cell;
+    let row: Vec<Cell> = cells.iter().cloned().map(Cell::from_state_index).collect();
+    seed_1d(state, &row)
+}
 
-        if last_board.0[i] != new_val_for_cell {
-            State::draw_canvas_cell(&mut state.canvas_buf, i, new_val_for_cell);
+/// Sets how the edges of the board are treated.  Valid values are "bounded", "torus", "klein",
+/// "mirror", and "alive".  Returns `false` and leaves the current topology in place if the name
+/// isn't recognized.
+#[wasm_bindgen]
+pub fn set_topology(name: &str) -> bool {
+    match Topology::parse(name) {
+        Ok(topology) => {
+            let state = state();
+            state.topology = topology;
+            state.cycles.invalidate();
+            true
+        }
+        Err(err) => {
+            error!("{}", err);
+            false
         }
     }
-    state.cur_buf_1 = !state.cur_buf_1;
+}
+
+/// Sets the color used to draw cells in the given state, where 0 is dead, 1 is alive, and 2 and
+/// up are the dying states of the active Generations rule.  Returns `false` if the active rule
+/// doesn't have that many states.
+#[wasm_bindgen]
+pub fn set_state_color(cell_state: u8, r: u8, g: u8, b: u8) -> bool {
+    let state = state();
+    match state.canvas.palette.get_mut(cell_state as usize) {
+        Some(color) => *color = [r, g, b],
+        None => {
+            error!(
+                "The active rule has no state {}; it only has {}",
+                cell_state,
+                state.state_count()
+            );
+            return false;
+        }
+    }
+
+    state.redraw();
+    state.canvas.render();
+    true
+}
+
+/// Sets how cells are colored.  Valid modes are "states" (the default, which uses the state
+/// colors), "age", "fade", and "heatmap".  Returns `false` if the mode isn't recognized.
+#[wasm_bindgen]
+pub fn set_render_mode(name: &str) -> bool {
+    let mode = match RenderMode::parse(name) {
+        Ok(mode) => mode,
+        Err(err) => {
+            error!("{}", err);
+            return false;
+        }
+    };
+
+    let state = state();
+    let cells = if state.cur_buf_1 {
+        &state.buf1.cells
+    } else {
+        &state.buf2.cells
+    };
+    state.renderer.set_mode(mode, cells);
+    state.redraw();
+    state.canvas.render();
+    true
+}
+
+/// Sets the gradient used by the "age", "fade", or "heatmap" render mode from a flat array of
+/// evenly spaced RGB colors.  Returns `false` if the mode or colors are invalid.
+#[wasm_bindgen]
+pub fn set_render_gradient(mode: &str, rgb: &[u8]) -> bool {
+    let state = state();
+    let gradient = RenderMode::parse(mode).and_then(|mode| {
+        let gradient = Gradient::from_rgb(rgb)?;
+        match state.renderer.gradient_mut(mode) {
+            Some(target) => {
+                *target = gradient;
+                Ok(())
+            }
+            None => Err("The \"states\" render mode uses the state colors".to_owned()),
+        }
+    });
+    if let Err(err) = gradient {
+        error!("{}", err);
+        return false;
+    }
+
+    state.redraw();
+    state.canvas.render();
+    true
+}
+
+/// Sets the number of generations over which the "age" and "fade" gradients are spread
+#[wasm_bindgen]
+pub fn set_render_span(generations: u32) {
+    let state = state();
+    state.renderer.span = generations.max(1);
+    state.redraw();
+    state.canvas.render();
+}
+
+/// Returns the number of generations since the cell at (`x`, `y`) was last born or died, or -1 if
+/// ages aren't being tracked because the "states" render mode is active
+#[wasm_bindgen]
+pub fn get_cell_age(x: usize, y: usize) -> f64 {
+    let state = state();
+    let width = state.buf1.width;
+    if x >= width || y >= state.buf1.height {
+        error!("({}, {}) is outside of the board", x, y);
+        return -1.;
+    }
+
+    match state.renderer.ages {
+        Some(ref ages) => ages.ages[y * width + x] as f64,
+        None => -1.,
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_state(canvas_pattern: &[u8]) {
+    let state = state();
+    state.snapshot_board();
+
+    for (i, cell) in canvas_pattern.iter().enumerate() {
+        let cell_state = if *cell == 0 { Cell::Dead } else { Cell::Alive };
+        state.set_cell_at_index(i, cell_state);
+    }
+
+    state.canvas.render();
+}
+
+/// Writes a freshly parsed pattern onto the board with its top-left corner at (`x`, `y`),
+/// switching to the pattern's rule if it specifies one.  Parse errors are passed back to the JS
+/// as a `PatternError`.
+fn load_pattern(parsed: Result<Pattern, PatternError>, x: isize, y: isize) -> Result<(), JsValue> {
+    let pattern = parsed.map_err(|err| {
+        error!("{}", err);
+        JsValue::from(err)
+    })?;
+
+    let state = state();
+    state.apply_pattern_rule(&pattern);
+    state.place_pattern(&pattern, x, y);
+    state.canvas.render();
+    Ok(())
+}
+
+/// Returns the bounding box of all live cells on the board as a pattern tagged with the active
+/// rule
+fn export_pattern() -> Pattern {
+    let state = state();
+    let mut pattern = Pattern::from_board(state.get_cur_buf());
+    pattern.rule = Some(state.rulestring());
+    pattern
+}
+
+/// Parses a pattern in the RLE format and writes it onto the board with its top-left corner at
+/// (`x`, `y`).  If the pattern specifies a rule, it becomes the active rule.
+#[wasm_bindgen]
+pub fn load_rle(text: &str, x: isize, y: isize) -> Result<(), JsValue> {
+    load_pattern(rle::parse(text), x, y)
+}
+
+/// Encodes the bounding box of all live cells on the board as RLE, including the active rule
+#[wasm_bindgen]
+pub fn export_rle() -> String {
+    rle::encode(&export_pattern())
+}
+
+/// Parses a pattern in the plaintext `.cells` format and writes it onto the board with its
+/// top-left corner at (`x`, `y`).
+#[wasm_bindgen]
+pub fn load_plaintext(text: &str, x: isize, y: isize) -> Result<(), JsValue> {
+    load_pattern(plaintext::parse(text), x, y)
+}
+
+#[wasm_bindgen]
+pub fn export_plaintext() -> String {
+    plaintext::encode(&export_pattern())
+}
+
+/// Parses a pattern in the Life 1.06 format and writes it onto the board with the top-left
+/// corner of its bounding box at (`x`, `y`).
+#[wasm_bindgen]
+pub fn load_life106(text: &str, x: isize, y: isize) -> Result<(), JsValue> {
+    load_pattern(life106::parse(text), x, y)
+}
+
+#[wasm_bindgen]
+pub fn export_life106() -> String {
+    life106::encode(&export_pattern())
+}
+
+/// Parses a pattern in any of the supported formats and stamps it onto the board with the
+/// top-left corner of its transformed bounding box at (`x`, `y`).  The pattern is mirrored
+/// left-to-right if `flip` is set and then rotated clockwise by `rotation` 90 degree turns.
+/// `mode` is one of "overwrite", "or", or "xor".  The active rule is left alone, even if the
+/// pattern specifies one.  Both parse errors and an unknown `mode` are passed back to the JS as a
+/// `PatternError`, with a `line` of 0 for the latter.
+#[wasm_bindgen]
+pub fn stamp_pattern(
+    pattern: &str,
+    x: isize,
+    y: isize,
+    rotation: u32,
+    flip: bool,
+    mode: &str,
+) -> Result<(), JsValue> {
+    let mode = BlendMode::parse(mode).map_err(|err| {
+        let err = PatternError::invalid_argument(err);
+        error!("{}", err);
+        JsValue::from(err)
+    })?;
+    let pattern = patterns::parse_any(pattern).map_err(|err| {
+        error!("{}", err);
+        JsValue::from(err)
+    })?;
+
+    let state = state();
+    state.stamp_pattern(&pattern.transformed(rotation, flip), x, y, mode);
+    state.canvas.render();
+    Ok(())
+}
+
+/// Fills the board with a random soup in which each cell is alive with probability `density`.
+/// The same `seed` and `density` always produce the same soup.  `region` is either empty to fill
+/// the whole board or `[x0, y0, x1, y1]`, the inclusive corners of the rectangle to fill; the
+/// rest of the board is left untouched.  Returns `false` if the region is invalid.
+#[wasm_bindgen]
+pub fn set_random_state(seed: u32, density: f64, region: &[u32]) -> bool {
+    let state = state();
+    let (width, height) = (state.buf1.width, state.buf1.height);
+    let region = match *region {
+        [] => Selection::from_corners(
+            (0, 0),
+            (width as isize - 1, height as isize - 1),
+            width,
+            height,
+        ),
+        [x0, y0, x1, y1] => Selection::from_corners(
+            (x0 as isize, y0 as isize),
+            (x1 as isize, y1 as isize),
+            width,
+            height,
+        ),
+        _ => {
+            error!("Soup regions must be empty or `[x0, y0, x1, y1]`");
+            return false;
+        }
+    };
+    let region = match region {
+        Some(region) => region,
+        None => {
+            error!("The soup region doesn't overlap the board");
+            return false;
+        }
+    };
+
+    state.snapshot_board();
+    soup::seed_rng(seed);
+    for i in region.indices(width) {
+        state.set_cell_at_index(i, soup::random_cell(density));
+    }
+
+    state.canvas.render();
+    true
+}
+
+/// Selects the rectangle spanning the cells at (`x0`, `y0`) and (`x1`, `y1`), inclusive, clipped
+/// to the board.  Returns `false` and clears the selection if none of it lies on the board.
+#[wasm_bindgen]
+pub fn set_selection(x0: isize, y0: isize, x1: isize, y1: isize) -> bool {
+    let state = state();
+    let selection =
+        Selection::from_corners((x0, y0), (x1, y1), state.buf1.width, state.buf1.height);
+    state.select(selection);
+    state.canvas.render();
+    selection.is_some()
+}
+
+#[wasm_bindgen]
+pub fn deselect() {
+    let state = state();
+    state.select(None);
+    state.canvas.render();
+}
+
+/// Returns the active selection, logging an error if there isn't one
+fn get_selection(state: &State) -> Option<Selection> {
+    if state.selection.is_none() {
+        error!("Nothing is selected");
+    }
+    state.selection
+}
+
+/// Copies the selected cells into the clipboard.  Returns `false` if nothing is selected.
+#[wasm_bindgen]
+pub fn copy_selection() -> bool {
+    let state = state();
+    match get_selection(state) {
+        Some(selection) => {
+            state.clipboard = Some(selection.extract(state.get_cur_buf()));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Sets every selected cell to `f(cell)`.  The whole edit is undone at once.
+fn edit_selection(f: impl Fn(Cell) -> Cell) -> bool {
+    let state = state();
+    let selection = match get_selection(state) {
+        Some(selection) => selection,
+        None => return false,
+    };
+
+    state.snapshot_board();
+    for i in selection.indices(state.buf1.width) {
+        let cell = f(state.get_cur_buf().cells[i]);
+        state.set_cell_at_index(i, cell);
+    }
+    state.canvas.render();
+    true
+}
+
+/// Copies the selected cells into the clipboard and then clears them
+#[wasm_bindgen]
+pub fn cut_selection() -> bool {
+    copy_selection() && edit_selection(|_| Cell::Dead)
+}
+
+/// Kills every selected cell
+#[wasm_bindgen]
+pub fn clear_selection() -> bool {
+    edit_selection(|_| Cell::Dead)
+}
+
+/// Kills every selected live cell and brings every other selected cell to life
+#[wasm_bindgen]
+pub fn invert_selection() -> bool {
+    edit_selection(|cell| {
+        if cell.is_alive() {
+            Cell::Dead
+        } else {
+            Cell::Alive
+        }
+    })
+}
+
+/// Sets each selected cell to alive with probability `density` and to dead otherwise
+#[wasm_bindgen]
+pub fn fill_selection_random(density: f64) -> bool {
+    edit_selection(|_| soup::random_cell(density))
+}
+
+/// Overwrites the board with the clipboard with its top-left corner at (`x`, `y`) and selects
+/// the pasted cells.  Returns `false` if nothing has been copied.
+#[wasm_bindgen]
+pub fn paste(x: isize, y: isize) -> bool {
+    let state = state();
+    let clipboard = match state.clipboard.take() {
+        Some(clipboard) => clipboard,
+        None => {
+            error!("Nothing has been copied");
+            return false;
+        }
+    };
+
+    state.stamp_pattern(&clipboard, x, y, BlendMode::Overwrite);
+    let selection = Selection::from_corners(
+        (x, y),
+        (
+            x + clipboard.width as isize - 1,
+            y + clipboard.height as isize - 1,
+        ),
+        state.buf1.width,
+        state.buf1.height,
+    );
+    state.select(selection);
+    state.clipboard = Some(clipboard);
+    state.canvas.render();
+    true
+}
+
+/// Switches between the bit-packed engine (the default) and the per-cell reference engine
+#[wasm_bindgen]
+pub fn set_packed_engine(enabled: bool) {
+    let state = state();
+    state.use_packed_engine = enabled;
+    if !enabled {
+        state.packed = None;
+    }
+}
+
+/// Advances the board by one generation with the per-cell engine, which is kept around as the
+/// reference that the bit-packed engine is tested against.
+fn tick_reference(state: &mut State) -> Transitions {
+    state.packed = None;
+    let (last_board, target_board): (&Board, &mut Board) = if state.cur_buf_1 {
+        (&state.buf1, &mut state.buf2)
+    } else {
+        (&state.buf2, &mut state.buf1)
+    };
+
+    let mut transitions = Transitions::new(last_board.width);
+    for i in 0..last_board.cell_count() {
+        let new_val_for_cell = get_next_cell_state(last_board, &state.rule, state.topology, i);
+        target_board.cells[i] = new_val_for_cell;
+
+        if last_board.cells[i] != new_val_for_cell {
+            transitions.record(i, last_board.cells[i], new_val_for_cell);
+            state.canvas.draw_cell(i, new_val_for_cell);
+        }
+    }
+    state.cur_buf_1 = !state.cur_buf_1;
+    transitions
+}
+
+/// Advances the board by one generation with the bit-packed engine, writing only the cells that
+/// changed back to the current buffer and the canvas.
+fn tick_packed(state: &mut State) -> Transitions {
+    let packed = match state.packed.take() {
+        Some(packed) => packed,
+        None => {
+            // Dying cells left over from a Generations rule can't be represented in the packed
+            // board.  They'd die during this generation anyway.
+            for i in 0..state.buf1.cell_count() {
+                if let Cell::Dying(_) = state.get_cur_buf().cells[i] {
+                    state.write_cell(i, Cell::Dead);
+                }
+            }
+            BitBoard::from_board(state.get_cur_buf())
+        }
+    };
+    let next = packed.step(&state.rule, state.topology);
+
+    let cur_buf = if state.cur_buf_1 {
+        &mut state.buf1
+    } else {
+        &mut state.buf2
+    };
+    let canvas = &mut state.canvas;
+    let mut transitions = Transitions::new(cur_buf.width);
+    packed.for_each_diff(&next, |i, cell| {
+        transitions.record(i, cur_buf.cells[i], cell);
+        cur_buf.cells[i] = cell;
+        canvas.draw_cell(i, cell);
+    });
+    state.packed = Some(next);
+    transitions
+}
+
+/// Advances the board by one generation under the active Larger than Life rule
+fn tick_ltl(state: &mut State) -> Transitions {
+    state.packed = None;
+    let ltl_rule = match state.ltl_rule {
+        Some(ref ltl_rule) => ltl_rule,
+        None => return Transitions::default(),
+    };
+    let (last_board, target_board): (&Board, &mut Board) = if state.cur_buf_1 {
+        (&state.buf1, &mut state.buf2)
+    } else {
+        (&state.buf2, &mut state.buf1)
+    };
+
+    ltl_rule.step(last_board, state.topology, target_board);
+    let mut transitions = Transitions::new(last_board.width);
+    for i in 0..last_board.cell_count() {
+        if last_board.cells[i] != target_board.cells[i] {
+            transitions.record(i, last_board.cells[i], target_board.cells[i]);
+            state.canvas.draw_cell(i, target_board.cells[i]);
+        }
+    }
+    state.cur_buf_1 = !state.cur_buf_1;
+    transitions
+}
+
+/// Advances the board by one generation under the active automaton
+fn tick_automaton(state: &mut State) -> Transitions {
+    state.packed = None;
+    let automaton = match state.automaton {
+        Some(automaton) => automaton,
+        None => return Transitions::default(),
+    };
+    let (last_board, target_board): (&Board, &mut Board) = if state.cur_buf_1 {
+        (&state.buf1, &mut state.buf2)
+    } else {
+        (&state.buf2, &mut state.buf1)
+    };
+
+    automaton.step(last_board, state.topology, target_board);
+    let mut transitions = Transitions::new(last_board.width);
+    for i in 0..last_board.cell_count() {
+        if last_board.cells[i] != target_board.cells[i] {
+            transitions.record(i, last_board.cells[i], target_board.cells[i]);
+            state.canvas.draw_cell(i, target_board.cells[i]);
+        }
+    }
+    state.cur_buf_1 = !state.cur_buf_1;
+    transitions
+}
+
+/// Advances the active 1D rule by one generation, writing it to the row below the current one or,
+/// once the board is full, scrolling the board up by a row and writing it to the bottom row
+fn tick_1d(state: &mut State) -> Transitions {
+    state.packed = None;
+    let elementary = match state.elementary {
+        Some(elementary) => elementary,
+        None => return Transitions::default(),
+    };
+    let (last_board, target_board): (&Board, &mut Board) = if state.cur_buf_1 {
+        (&state.buf1, &mut state.buf2)
+    } else {
+        (&state.buf2, &mut state.buf1)
+    };
+
+    let width = last_board.width;
+    let row_ix = elementary.row_of(state.generation, last_board.height);
+    let row = &last_board.cells[row_ix * width..(row_ix + 1) * width];
+    target_board.cells.copy_from_slice(&last_board.cells);
+    // Whatever is currently drawn where the new row goes
+    let (next_row_ix, drawn) = if row_ix + 1 < last_board.height {
+        let next_row_ix = row_ix + 1;
+        (
+            next_row_ix,
+            &last_board.cells[next_row_ix * width..(next_row_ix + 1) * width],
+        )
+    } else {
+        // The top row wraps around to the bottom, where it's overwritten by the new row
+        target_board.cells.rotate_left(width);
+        state.canvas.scroll_up(1);
+        (row_ix, row)
+    };
+
+    let next_row = &mut target_board.cells[next_row_ix * width..(next_row_ix + 1) * width];
+    elementary.rule.step(row, state.topology, next_row);
+    // Births and deaths are counted between consecutive rows rather than between the boards, so
+    // the board's statistics can't be derived from them
+    state.stats.invalidate();
+    let mut transitions = Transitions::new(width);
+    for x in 0..width {
+        if row[x] != next_row[x] {
+            transitions.record(next_row_ix * width + x, row[x], next_row[x]);
+        }
+        if drawn[x] != next_row[x] {
+            state.canvas.draw_cell(next_row_ix * width + x, next_row[x]);
+        }
+    }
+    state.cur_buf_1 = !state.cur_buf_1;
+    transitions
+}
+
+/// Advances the board by one generation under the active block rule
+fn tick_margolus(state: &mut State) -> Transitions {
+    state.packed = None;
+    let rule = match state.margolus {
+        Some(rule) => rule,
+        None => return Transitions::default(),
+    };
+    let (last_board, target_board): (&Board, &mut Board) = if state.cur_buf_1 {
+        (&state.buf1, &mut state.buf2)
+    } else {
+        (&state.buf2, &mut state.buf1)
+    };
+
+    rule.step(last_board, state.topology, state.generation, target_board);
+    let mut transitions = Transitions::new(last_board.width);
+    for i in 0..last_board.cell_count() {
+        if last_board.cells[i] != target_board.cells[i] {
+            transitions.record(i, last_board.cells[i], target_board.cells[i]);
+            state.canvas.draw_cell(i, target_board.cells[i]);
+        }
+    }
+    state.cur_buf_1 = !state.cur_buf_1;
+    transitions
+}
+
+/// Makes sure that the active rule can be simulated by the unbounded engines, logging an error if
+/// it can't.
+fn check_unbounded_rule(state: &State) -> bool {
+    let rule = &state.rule;
+    if let Some(automaton) = state.automaton {
+        error!("Unbounded planes can't simulate {}", automaton);
+        false
+    } else if state.elementary.is_some() {
+        error!("Unbounded planes can't simulate 1D rules");
+        false
+    } else if state.margolus.is_some() {
+        error!("Unbounded planes can't simulate Margolus rules");
+        false
+    } else if state.ltl_rule.is_some() {
+        error!("Unbounded planes can't simulate Larger than Life rules");
+        false
+    } else if rule.birth[0] {
+        error!("Unbounded planes can't simulate rules where cells are born with zero neighbors");
+        false
+    } else if !rule.is_life_like() {
+        error!("Unbounded planes can only simulate two-state rules on the square grid");
+        false
+    } else {
+        true
+    }
+}
+
+/// Takes the active unbounded universe, or creates a new HashLife universe from the current board
+/// if there isn't one.
+fn take_universe(state: &mut State) -> Universe {
+    if let Some(universe) = state.universe.take() {
+        return universe;
+    }
+
+    // The board mirrors the universe from here on, so it's recorded for once the universe exits
+    state.snapshot_board();
+    let viewport = state.viewport;
+    let cur_buf = state.get_cur_buf();
+    let live_cells: Vec<(i64, i64)> = cur_buf
+        .cells
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| cell.is_alive())
+        .map(|(i, _)| (i % cur_buf.width, i / cur_buf.width))
+        .map(|(x, y)| viewport.to_universe(x, y))
+        .collect();
+    Universe::HashLife(HashLife::from_cells(state.rule, live_cells))
+}
+
+/// Advances the HashLife universe by 2^`log2_generations` generations, creating it from the
+/// current board or sparse plane first if needed.  The universe is unbounded, so the topology is
+/// ignored while it's in use.
+#[wasm_bindgen]
+pub fn step_generations(log2_generations: u32) {
+    let state = state();
+    if !check_unbounded_rule(state) {
+        return;
+    }
+
+    let mut universe = take_universe(state).into_hashlife(state.rule);
+    universe.set_rule(state.rule);
+    universe.step(log2_generations);
+    state.universe = Some(Universe::HashLife(universe));
+
+    state.render_viewport();
+    state.canvas.render();
+}
+
+/// Switches to simulating an unbounded sparse plane, created from the current board or HashLife
+/// universe.  Subsequent calls to `tick` advance the plane one generation at a time.
+#[wasm_bindgen]
+pub fn enable_sparse_plane() {
+    let state = state();
+    if !check_unbounded_rule(state) {
+        return;
+    }
+
+    let plane = take_universe(state).into_sparse(state.rule);
+    state.universe = Some(Universe::Sparse(plane));
+}
+
+/// Moves the window onto the unbounded universe so that the cell at (`x`, `y`) is in the top-left
+/// corner of the board.  Each board cell covers a `2^zoom` x `2^zoom` block of the universe.
+#[wasm_bindgen]
+pub fn set_viewport(x: i32, y: i32, zoom: u32) {
+    let state = state();
+    state.viewport = Viewport::new(x as i64, y as i64, zoom);
+    if state.universe.is_some() {
+        state.render_viewport();
+        state.canvas.render();
+    }
+}
+
+/// Drops the unbounded universe, leaving the board with whatever was visible through the viewport
+#[wasm_bindgen]
+pub fn exit_unbounded_plane() {
+    state().universe = None;
+}
+
+/// Returns the number of generations the unbounded universe has been advanced, or -1 if there
+/// isn't one
+#[wasm_bindgen]
+pub fn get_universe_generation() -> f64 {
+    match state().universe {
+        Some(ref universe) => universe.generation() as f64,
+        None => -1.,
+    }
+}
+
+/// Advances the board by one generation with whichever engine suits the active rule, recording
+/// the current generation in the history first
+fn advance_board(state: &mut State) {
+    let generation = state.generation;
+    // Generations of reversible rules are undone by running them backwards instead
+    match state.margolus {
+        Some(rule) => state
+            .history
+            .push_reversible(generation, rule, state.topology),
+        None => state.snapshot_board(),
+    }
+    if !state.cycles.is_tracking() {
+        state.record_cycle();
+    }
+
+    // The packed engine only stores whether each cell is alive on a square grid, so Generations
+    // rules and rules for other grids always run on the reference engine
+    let transitions = if state.elementary.is_some() {
+        tick_1d(state)
+    } else if state.margolus.is_some() {
+        tick_margolus(state)
+    } else if state.automaton.is_some() {
+        tick_automaton(state)
+    } else if state.ltl_rule.is_some() {
+        tick_ltl(state)
+    } else if state.use_packed_engine && state.rule.is_life_like() {
+        tick_packed(state)
+    } else {
+        tick_reference(state)
+    };
+    state.generation += 1;
+    state.record_cycle();
+
+    let cur_buf = if state.cur_buf_1 {
+        &state.buf1
+    } else {
+        &state.buf2
+    };
+    state.stats.record(state.generation, cur_buf, transitions);
+
+    // Cells change color as they age, so the engines' drawing of only the changed cells isn't
+    // enough
+    if let Some(ref mut ages) = state.renderer.ages {
+        ages.advance(&cur_buf.cells);
+        if !state.canvas.suspended {
+            state.redraw();
+        }
+    }
+}
+
+/// Advances the board by `generations` generations without drawing any of the intermediate ones,
+/// then draws the cells that differ from where they started
+fn advance_board_by(state: &mut State, generations: u64) {
+    if generations == 1 {
+        advance_board(state);
+        return;
+    }
+
+    let before = state.get_cur_buf().cells.clone();
+    state.canvas.suspended = true;
+    for _ in 0..generations {
+        advance_board(state);
+    }
+    state.canvas.suspended = false;
+
+    if state.renderer.ages.is_some() {
+        state.redraw();
+        return;
+    }
+    for (i, &old_val) in before.iter().enumerate() {
+        let cell = state.get_cur_buf().cells[i];
+        if cell != old_val {
+            state.draw_cell(i, cell);
+        }
+    }
+}
+
+/// Advances the unbounded universe by `generations` generations and copies the area visible
+/// through the viewport onto the board.  Returns `false` if the active rule can't be simulated on
+/// an unbounded plane.
+fn advance_universe(state: &mut State, generations: u64) -> bool {
+    if !check_unbounded_rule(state) {
+        return false;
+    }
+
+    let universe = state.universe.as_mut().unwrap();
+    universe.set_rule(state.rule);
+    for _ in 0..generations {
+        universe.step();
+    }
+    state.render_viewport();
+    true
+}
+
+#[wasm_bindgen]
+pub fn tick() {
+    tick_n(1);
+}
+
+/// Advances the board, or the unbounded universe if one is active, by `n` generations and renders
+/// only the last of them.  Every generation is still recorded in the history and statistics.
+#[wasm_bindgen]
+pub fn tick_n(n: u32) {
+    let state = state();
+    if state.universe.is_some() {
+        if !advance_universe(state, n as u64) {
+            return;
+        }
+    } else {
+        advance_board_by(state, n as u64);
+    }
+    state.canvas.render();
+}
+
+/// Splits the board into objects, classifies each one, and returns a JSON array with the number of
+/// each kind of object, most common first.  Each entry has the object's `code`, its `name` (only
+/// for well-known objects under Conway's Life), its `kind` ("still_life", "oscillator",
+/// "spaceship", or "unstable"), `period`, displacement per period `dx` and `dy` (normalized so that
+/// `dx >= dy >= 0`), `population`, and `count`.  Returns `undefined` if the active rule isn't
+/// supported.
+#[wasm_bindgen]
+pub fn get_census() -> Option<String> {
+    let state = state();
+    if !check_census_rule(state) {
+        return None;
+    }
+
+    let cur_buf = if state.cur_buf_1 {
+        &state.buf1
+    } else {
+        &state.buf2
+    };
+    let census = census::take_census(cur_buf, &state.rule, state.topology);
+    Some(census::census_to_json(&census))
+}
+
+/// Makes sure that objects can be classified under the active rule, logging an error if they can't
+fn check_census_rule(state: &State) -> bool {
+    if state.automaton.is_some()
+        || state.elementary.is_some()
+        || state.margolus.is_some()
+        || state.ltl_rule.is_some()
+        || !state.rule.is_life_like()
+        || state.rule.birth[0]
+    {
+        error!("Censuses can only be taken under two-state rules on the square grid without B0");
+        false
+    } else {
+        true
+    }
+}
+
+/// Simulates `count` random soups with consecutive seeds starting at `first_seed` off-screen under
+/// the active rule and topology, leaving the board untouched.  Each soup is `width` x `height`
+/// cells with the given `density` and is run until it settles into a cycle or for
+/// `max_generations` generations.  Returns a JSON object keyed by seed with each soup's
+/// `lifespan` (the generation at which it settled, or null), `period`, `final_population`, and
+/// the census entries of any `rare_objects` in its final state.  Rare objects that were destroyed
+/// before the end aren't reported.  Any soup can be replayed with
+/// `set_random_state(seed, density, [])` on a board of the same size.
+#[wasm_bindgen]
+pub fn search_soups(
+    first_seed: u32,
+    count: u32,
+    width: usize,
+    height: usize,
+    density: f64,
+    max_generations: f64,
+) -> Option<String> {
+    let state = state();
+    if !check_census_rule(state) {
+        return None;
+    }
+
+    let params = SearchParams {
+        width,
+        height,
+        density,
+        rule: state.rule,
+        topology: state.topology,
+        max_generations: max_generations as u64,
+    };
+    let results: Vec<_> = (0..count)
+        .map(|i| search::run_soup(first_seed.wrapping_add(i), &params))
+        .collect();
+    Some(search::results_to_json(&results))
+}
+
+/// Returns the number of generations that the board has been advanced
+#[wasm_bindgen]
+pub fn get_generation() -> f64 {
+    state().generation as f64
+}
+
+/// Returns the population, births, deaths, and bounding box of the board as of the most recent
+/// generation, or `undefined` if the board hasn't been advanced yet
+#[wasm_bindgen]
+pub fn get_stats() -> Option<GenerationStats> {
+    state().stats.latest()
+}
+
+/// Returns the statistics of recent generations, oldest first, as a flat array of
+/// `[population, births, deaths]` triples.  The first triple is for the generation returned by
+/// `get_stats_series_start`.
+#[wasm_bindgen]
+pub fn get_stats_series() -> Vec<u32> {
+    state().stats.to_flat_vec()
+}
+
+/// Returns the generation of the oldest entry in the stats series, or -1 if it's empty
+#[wasm_bindgen]
+pub fn get_stats_series_start() -> f64 {
+    match state().stats.start_generation() {
+        Some(generation) => generation as f64,
+        None => -1.,
+    }
+}
+
+/// Returns the period of the cycle that the board has fallen into and the generation at which it
+/// started, or `undefined` if the board hasn't repeated itself since it was last edited.  Static
+/// boards have a period of 1.
+#[wasm_bindgen]
+pub fn get_cycle_info() -> Option<CycleInfo> {
+    state().cycles.cycle
+}
+
+/// Undoes the most recent entry in the history, which is a generation, an edit made with
+/// `set_pixel`, or an overwrite of the board.  Returns `false` if there's nothing left to undo.
+fn undo(state: &mut State) -> bool {
+    match state.history.pop() {
+        Some(HistoryEntry::Snapshot {
+            generation,
+            cells,
+            seed_generation,
+        }) => {
+            for (i, cell) in cells.decompress().into_iter().enumerate() {
+                state.write_cell(i, cell);
+            }
+            state.generation = generation;
+            if let (Some(elementary), Some(seed_generation)) =
+                (state.elementary.as_mut(), seed_generation)
+            {
+                elementary.seed_generation = seed_generation;
+            }
+            state.stats.truncate_after(generation);
+            true
+        }
+        Some(HistoryEntry::Edit { index, before }) => {
+            state.write_cell(index, before);
+            true
+        }
+        Some(HistoryEntry::Reversible {
+            generation,
+            rule,
+            topology,
+        }) => {
+            let cur_buf = if state.cur_buf_1 {
+                &state.buf1
+            } else {
+                &state.buf2
+            };
+            let mut previous = Board {
+                width: cur_buf.width,
+                height: cur_buf.height,
+                cells: cur_buf.cells.clone(),
+            };
+            rule.step_back(cur_buf, topology, generation, &mut previous);
+            for (i, &cell) in previous.cells.iter().enumerate() {
+                state.write_cell(i, cell);
+            }
+            state.generation = generation;
+            state.stats.truncate_after(generation);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Undoes the last generation or edit.  Returns `false` if the history is empty.  The history only
+/// covers the board, so this does nothing while an unbounded universe is active.
+#[wasm_bindgen]
+pub fn step_back() -> bool {
+    let state = state();
+    if state.universe.is_some() {
+        error!("Stepping back isn't supported while simulating an unbounded plane");
+        return false;
+    }
+
+    let undone = undo(state);
+    state.canvas.render();
+    undone
+}
+
+/// Moves the board to the given generation, rewinding through the history or simulating forward
+/// as needed.  Returns `false` if the generation isn't a non-negative number, is older than
+/// anything left in the history, or is more than `MAX_SEEK_GENERATIONS` ahead of the board.
+#[wasm_bindgen]
+pub fn seek(generation: f64) -> bool {
+    let state = state();
+    if state.universe.is_some() {
+        error!("Seeking isn't supported while simulating an unbounded plane");
+        return false;
+    }
+    if !generation.is_finite() || generation < 0.0 {
+        error!("Can't seek to generation {}", generation);
+        return false;
+    }
+
+    let generation = generation as u64;
+    if generation < state.generation {
+        match state.history.oldest_generation() {
+            Some(oldest) if oldest <= generation => (),
+            _ => {
+                error!("Generation {} is no longer in the history", generation);
+                return false;
+            }
+        }
+
+        while state.generation > generation && undo(state) {}
+    } else {
+        let generations = generation - state.generation;
+        if generations > MAX_SEEK_GENERATIONS {
+            error!(
+                "Generation {} is {} generations ahead; at most {} can be simulated at once",
+                generation, generations, MAX_SEEK_GENERATIONS
+            );
+            return false;
+        }
+        advance_board_by(state, generations);
+    }
+
+    state.canvas.render();
+    true
+}
+
+#[test]
+fn reversible_generations_undo_across_overwrites() {
+    // The board starts out as a random soup, which is all alive until the RNG is seeded
+    soup::seed_rng(25);
+    let mut state = State::new(12, 10, 1);
+    state.set_block_rule(BlockRule::named("critters").unwrap());
+    let start = state.get_cur_buf().cells.clone();
+
+    for _ in 0..3 {
+        advance_board(&mut state);
+    }
+    let before_load = state.get_cur_buf().cells.clone();
+    let pattern = rle::parse("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+    state.place_pattern(&pattern, 4, 4);
+    let toggled = if state.get_cur_buf().cells[0].is_alive() {
+        Cell::Dead
+    } else {
+        Cell::Alive
+    };
+    state.edit_cell(0, toggled);
+    for _ in 0..3 {
+        advance_board(&mut state);
+    }
+
+    for _ in 0..5 {
+        assert!(undo(&mut state));
+    }
+    assert_eq!(state.generation, 3);
+    assert_eq!(state.get_cur_buf().cells, before_load);
+    while undo(&mut state) {}
+    assert_eq!(state.generation, 0);
+    assert_eq!(state.get_cur_buf().cells, start);
+}
+
+#[test]
+fn undoing_a_seed_restores_the_row_being_drawn() {
+    let mut state = State::new(9, 6, 1);
+    state.set_1d_rule(Rule1d::parse("W30").unwrap());
+    assert!(write_1d_seed(&mut state, &[Cell::Alive]));
+    // Fill the board so that it's scrolling by the time it's seeded again
+    for _ in 0..8 {
+        advance_board(&mut state);
+    }
+    let full = state.get_cur_buf().cells.clone();
+    assert!(write_1d_seed(&mut state, &[Cell::Alive, Cell::Alive]));
+    assert!(undo(&mut state));
+    assert_eq!(state.get_cur_buf().cells, full);
+
+    // The next generation scrolls the board and goes on the bottom row, not the second one
+    advance_board(&mut state);
+    let width = 9;
+    assert_eq!(state.get_cur_buf().cells[..5 * width], full[width..]);
+}
+
+#[test]
+fn block_rules_only_repeat_at_the_same_partition() {
+    let mut state = State::new(8, 8, 1);
+    state.set_block_rule(BlockRule::named("bbm").unwrap());
+    for i in 0..state.buf1.cell_count() {
+        state.set_cell_at_index(i, Cell::Dead);
+    }
+    // A pair of balls that sits still in the first partition but splits up in the second
+    state.set_cell_at_index(2 * 8 + 2, Cell::Alive);
+    state.set_cell_at_index(2 * 8 + 3, Cell::Alive);
+    let start = state.get_cur_buf().cells.clone();
+
+    advance_board(&mut state);
+    assert_eq!(state.get_cur_buf().cells, start);
+    assert_eq!(state.cycles.cycle, None);
+    advance_board(&mut state);
+    assert_ne!(state.get_cur_buf().cells, start);
+    assert_eq!(state.cycles.cycle, None);
+}
+
+#[test]
+fn leaving_an_automaton_restores_the_default_palette() {
+    let mut state = State::new(8, 8, 1);
+    state.set_automaton(Automaton::Wireworld);
+    state.set_rule(Rule::default());
+    state.set_cell_at_index(0, Cell::Alive);
+    assert_eq!(state.canvas.palette, DEFAULT_PALETTE.to_vec());
+    assert_eq!(&state.canvas.buf[0..4], &[255, 255, 255, 255]);
 
-    canvasRender(state.canvas_buf.as_ptr() as *const u8);
+    state.set_automaton(Automaton::Wireworld);
+    state.set_rulestring("B3/S23/C4").unwrap();
+    assert_eq!(state.canvas.palette[1], [255, 255, 255]);
+    assert_ne!(state.canvas.palette[2], Automaton::Wireworld.palette()[2]);
 }
@@ -0,0 +1,468 @@
+//! Larger than Life rules, which count live cells over a neighborhood of radius R rather than the
+//! eight adjacent cells.  Rulestrings use the `R5,C0,M1,S34..58,B34..45,NM` notation.
+//!
+//! Each neighborhood is compiled into a small set of weighted rectangles, and the number of live
+//! cells in each rectangle is read from a summed-area table of the board.  The cost of a
+//! generation is proportional to the number of rectangles rather than the area of the
+//! neighborhood, so Moore neighborhoods of any radius take constant time per cell.
+
+use std::fmt;
+
+use crate::{topology::Topology, Board, Cell};
+
+/// Largest supported neighborhood radius, matching Golly's limit
+pub const MAX_RADIUS: usize = 500;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Neighborhood {
+    /// Square of side `2R + 1` centered on the cell
+    Moore,
+    /// Diamond of cells within a Manhattan distance of `R`
+    VonNeumann,
+    /// Custom `(2R + 1)` x `(2R + 1)` kernel of per-cell weights in row-major order.  Each live
+    /// cell adds its weight to the count.
+    Weighted(Vec<u32>),
+}
+
+/// Rectangle of kernel cells with the same weight, given as inclusive offsets from the center
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct KernelRect {
+    left: isize,
+    top: isize,
+    right: isize,
+    bottom: isize,
+    weight: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LtlRule {
+    pub radius: usize,
+    /// Total number of cell states; values above 2 add Generations-style dying states
+    pub states: u8,
+    /// Whether the cell itself is counted as part of its neighborhood
+    pub include_center: bool,
+    /// Inclusive ranges of neighbor counts for which live cells survive
+    pub survival: Vec<(u32, u32)>,
+    /// Inclusive ranges of neighbor counts for which dead cells are born
+    pub birth: Vec<(u32, u32)>,
+    pub neighborhood: Neighborhood,
+    kernel: Vec<KernelRect>,
+}
+
+/// Splits the kernel weights into runs of equal weight along each row, then merges runs that
+/// repeat on consecutive rows into rectangles.
+fn compile_kernel(weights: &[u32], radius: usize) -> Vec<KernelRect> {
+    let size = radius * 2 + 1;
+    let r = radius as isize;
+    let mut rects = Vec::new();
+    let mut open: Vec<KernelRect> = Vec::new();
+
+    for row in 0..size {
+        let y = row as isize - r;
+        let row_weights = &weights[row * size..(row + 1) * size];
+        let mut next_open = Vec::new();
+
+        let mut col = 0;
+        while col < size {
+            let (start, weight) = (col, row_weights[col]);
+            while col < size && row_weights[col] == weight {
+                col += 1;
+            }
+            if weight == 0 {
+                continue;
+            }
+
+            let (left, right) = (start as isize - r, col as isize - 1 - r);
+            let continued = open
+                .iter()
+                .position(|rect| rect.left == left && rect.right == right && rect.weight == weight);
+            match continued {
+                Some(ix) => {
+                    let mut rect = open.swap_remove(ix);
+                    rect.bottom = y;
+                    next_open.push(rect);
+                }
+                None => next_open.push(KernelRect {
+                    left,
+                    top: y,
+                    right,
+                    bottom: y,
+                    weight,
+                }),
+            }
+        }
+
+        // Anything that didn't continue onto this row is finished
+        rects.append(&mut open);
+        open = next_open;
+    }
+
+    rects.append(&mut open);
+    rects
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, field: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value \"{}\" for {} in rulestring", value, field))
+}
+
+/// Parses a count range like `34..58` or a single count like `3`
+fn parse_range(range: &str) -> Result<(u32, u32), String> {
+    let mut bounds = range.splitn(2, "..");
+    let min = parse_number(bounds.next().unwrap(), "count range")?;
+    let max = match bounds.next() {
+        Some(max) => parse_number(max, "count range")?,
+        None => min,
+    };
+    if min > max {
+        return Err(format!("Count range \"{}\" is empty", range));
+    }
+    Ok((min, max))
+}
+
+fn parse_weights(weights: &str, radius: usize) -> Result<Vec<u32>, String> {
+    let size = radius * 2 + 1;
+    if weights.len() != size * size {
+        return Err(format!(
+            "A weighted neighborhood of radius {} needs {} weights but {} were given",
+            radius,
+            size * size,
+            weights.len()
+        ));
+    }
+
+    weights
+        .chars()
+        .map(|c| {
+            c.to_digit(16)
+                .ok_or_else(|| format!("Invalid weight '{}' in rulestring; expected 0-F", c))
+        })
+        .collect()
+}
+
+#[inline]
+fn in_ranges(ranges: &[(u32, u32)], count: u32) -> bool {
+    ranges
+        .iter()
+        .any(|&(min, max)| count >= min && count <= max)
+}
+
+/// Returns `true` if the rulestring looks like Larger than Life notation rather than B/S notation
+pub fn is_ltl_rulestring(rulestring: &str) -> bool {
+    let mut chars = rulestring.trim_start().chars();
+    match (chars.next(), chars.next()) {
+        (Some('R'), Some(c)) | (Some('r'), Some(c)) => c.is_ascii_digit(),
+        _ => false,
+    }
+}
+
+impl LtlRule {
+    pub fn new(
+        radius: usize,
+        states: u8,
+        include_center: bool,
+        survival: Vec<(u32, u32)>,
+        birth: Vec<(u32, u32)>,
+        neighborhood: Neighborhood,
+    ) -> Self {
+        let size = radius * 2 + 1;
+        let r = radius as isize;
+        let mut weights: Vec<u32> = match neighborhood {
+            Neighborhood::Moore => vec![1; size * size],
+            Neighborhood::VonNeumann => (0..size * size)
+                .map(|i| {
+                    let (x, y) = ((i % size) as isize - r, (i / size) as isize - r);
+                    (x.abs() + y.abs() <= r) as u32
+                })
+                .collect(),
+            Neighborhood::Weighted(ref weights) => weights.clone(),
+        };
+        if !include_center {
+            weights[radius * size + radius] = 0;
+        }
+
+        LtlRule {
+            radius,
+            states,
+            include_center,
+            survival,
+            birth,
+            kernel: compile_kernel(&weights, radius),
+            neighborhood,
+        }
+    }
+
+    /// Parses a rulestring like `R5,C0,M1,S34..58,B34..45,NM`.  `N` selects the neighborhood:
+    /// `NM` for Moore (the default), `NN` for von Neumann, or `NW` followed by `(2R + 1)^2` hex
+    /// digit weights for a custom kernel.  `S` and `B` may be followed by several ranges, as in
+    /// `S2..3,5..6`.
+    pub fn parse(rulestring: &str) -> Result<Self, String> {
+        let rulestring: String = rulestring.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut radius = None;
+        let mut states = 2;
+        let mut include_center = false;
+        let mut survival = None;
+        let mut birth = None;
+        let mut neighborhood = None;
+        let mut weights = None;
+
+        // Bare ranges get added to whichever of `S` or `B` came before them
+        let mut last_ranges: Option<&mut Vec<(u32, u32)>> = None;
+        for field in rulestring.split(',') {
+            let mut chars = field.chars();
+            let kind = chars.next().map(|c| c.to_ascii_uppercase());
+            let value = chars.as_str();
+            match kind {
+                Some('R') => {
+                    let r: usize = parse_number(value, "R")?;
+                    if !(1..=MAX_RADIUS).contains(&r) {
+                        return Err(format!("Radius must be between 1 and {}", MAX_RADIUS));
+                    }
+                    radius = Some(r);
+                }
+                Some('C') => {
+                    states = match parse_number::<u8>(value, "C")? {
+                        0 | 2 => 2,
+                        1 => return Err("A rule can't have only one state".to_owned()),
+                        states => states,
+                    }
+                }
+                Some('M') => {
+                    include_center = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(format!("Invalid value \"{}\" for M", value)),
+                    }
+                }
+                Some('S') | Some('B') => {
+                    let ranges = if kind == Some('S') {
+                        &mut survival
+                    } else {
+                        &mut birth
+                    };
+                    if ranges.is_some() {
+                        return Err(format!("Rulestring \"{}\" repeats a field", rulestring));
+                    }
+                    let ranges = ranges.get_or_insert_with(Vec::new);
+                    if !value.is_empty() {
+                        ranges.push(parse_range(value)?);
+                    }
+                    last_ranges = Some(ranges);
+                }
+                Some('N') => {
+                    let mut value_chars = value.chars();
+                    neighborhood = match value_chars.next().map(|c| c.to_ascii_uppercase()) {
+                        Some('M') if value.len() == 1 => Some(Neighborhood::Moore),
+                        Some('N') if value.len() == 1 => Some(Neighborhood::VonNeumann),
+                        Some('W') => {
+                            weights = Some(value_chars.as_str().to_owned());
+                            None
+                        }
+                        _ => return Err(format!("Unknown neighborhood \"{}\"", value)),
+                    };
+                }
+                Some(c) if c.is_ascii_digit() => match last_ranges {
+                    Some(ref mut ranges) => ranges.push(parse_range(field)?),
+                    None => return Err(format!("Unexpected count range \"{}\"", field)),
+                },
+                _ => return Err(format!("Invalid rulestring field \"{}\"", field)),
+            }
+        }
+
+        let radius = radius.ok_or("Rulestring is missing the radius field R")?;
+        let (survival, birth) = match (survival, birth) {
+            (Some(survival), Some(birth)) => (survival, birth),
+            _ => {
+                return Err(format!(
+                    "Rulestring \"{}\" must contain both an S and a B field",
+                    rulestring
+                ))
+            }
+        };
+        let neighborhood = match weights {
+            Some(weights) => Neighborhood::Weighted(parse_weights(&weights, radius)?),
+            None => neighborhood.unwrap_or(Neighborhood::Moore),
+        };
+
+        Ok(LtlRule::new(
+            radius,
+            states,
+            include_center,
+            survival,
+            birth,
+            neighborhood,
+        ))
+    }
+
+    /// Computes the next generation of `last` into `next`, which must have the same dimensions
+    pub fn step(&self, last: &Board, topology: Topology, next: &mut Board) {
+        let (width, height) = (last.width, last.height);
+        let r = self.radius;
+        // The board is padded by the radius on all sides with the cells that the topology puts
+        // there, so no lookups during the count need to worry about the edges.
+        let padded_width = width + r * 2;
+        let padded_height = height + r * 2;
+        let stride = padded_width + 1;
+
+        // `sat[y * stride + x]` holds the number of live cells above and to the left of (x, y)
+        let mut sat = vec![0u32; stride * (padded_height + 1)];
+        for y in 0..padded_height {
+            let mut row_sum = 0;
+            for x in 0..padded_width {
+                let cell = last.get(x as isize - r as isize, y as isize - r as isize, topology);
+                if cell.map(|cell| cell.is_alive()).unwrap_or(false) {
+                    row_sum += 1;
+                }
+                sat[(y + 1) * stride + x + 1] = sat[y * stride + x + 1] + row_sum;
+            }
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                let (center_x, center_y) = ((x + r) as isize, (y + r) as isize);
+                let count: u32 = self
+                    .kernel
+                    .iter()
+                    .map(|rect| {
+                        let left = (center_x + rect.left) as usize;
+                        let right = (center_x + rect.right) as usize + 1;
+                        let top = (center_y + rect.top) as usize;
+                        let bottom = (center_y + rect.bottom) as usize + 1;
+                        let live = (sat[bottom * stride + right] + sat[top * stride + left])
+                            - (sat[top * stride + right] + sat[bottom * stride + left]);
+                        live * rect.weight
+                    })
+                    .sum();
+
+                let i = y * width + x;
+                next.cells[i] = self.next_state(last.cells[i], count);
+            }
+        }
+    }
+
+    /// Returns the next state of a cell given the weighted count of its live neighbors
+    #[inline]
+    fn next_state(&self, cell: Cell, count: u32) -> Cell {
+        match cell {
+            Cell::Alive if in_ranges(&self.survival, count) => Cell::Alive,
+            Cell::Alive if self.states > 2 => Cell::Dying(2),
+            Cell::Dead if in_ranges(&self.birth, count) => Cell::Alive,
+            Cell::Dying(state) if state + 1 < self.states => Cell::Dying(state + 1),
+            _ => Cell::Dead,
+        }
+    }
+}
+
+impl fmt::Display for LtlRule {
+    /// Formats the rule in canonical notation, e.g. `R5,C0,M1,S34..58,B34..45,NM`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let states = if self.states > 2 { self.states } else { 0 };
+        write!(
+            f,
+            "R{},C{},M{},",
+            self.radius, states, self.include_center as u8
+        )?;
+        for (prefix, ranges) in &[("S", &self.survival), ("B", &self.birth)] {
+            write!(f, "{}", prefix)?;
+            for (i, (min, max)) in ranges.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{}..{}", min, max)?;
+            }
+            write!(f, ",")?;
+        }
+
+        match self.neighborhood {
+            Neighborhood::Moore => write!(f, "NM"),
+            Neighborhood::VonNeumann => write!(f, "NN"),
+            Neighborhood::Weighted(ref weights) => {
+                write!(f, "NW")?;
+                for weight in weights {
+                    write!(f, "{:X}", weight)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn ltl_counts_match_direct_sums() {
+    use crate::bitboard::test_board;
+
+    let rules = [
+        "R1,C0,M0,S2..3,B3..3,NM",
+        "R3,C0,M1,S10..20,B12..14,NM",
+        "R2,C4,M0,S3..5,7,B4..6,NN",
+        "R1,C0,M1,S3..6,B4..5,NW121202121",
+        "R4,C0,M1,S8..14,B9..12,NN",
+        "R2,C0,M0,S6..14,B7..10,NW1230004A0F2100112300F0012",
+    ];
+    let topologies = [Topology::Bounded, Topology::Torus, Topology::AliveEdges];
+
+    for rulestring in &rules {
+        let rule = LtlRule::parse(rulestring).unwrap();
+        assert_eq!(LtlRule::parse(&rule.to_string()).unwrap(), rule);
+
+        let size = rule.radius * 2 + 1;
+        let r = rule.radius as isize;
+        // Weight of each offset straight from the definition of the neighborhood, so that a
+        // badly compiled kernel doesn't go unnoticed
+        let weights: Vec<u32> = (0..size * size)
+            .map(|k| {
+                let (dx, dy) = ((k % size) as isize - r, (k / size) as isize - r);
+                if dx == 0 && dy == 0 && !rule.include_center {
+                    return 0;
+                }
+                match rule.neighborhood {
+                    Neighborhood::Moore => 1,
+                    Neighborhood::VonNeumann => (dx.abs() + dy.abs() <= r) as u32,
+                    Neighborhood::Weighted(ref weights) => weights[k],
+                }
+            })
+            .collect();
+
+        for &topology in &topologies {
+            let mut board = test_board(23, 17, 7);
+            for _ in 0..6 {
+                let mut next = test_board(23, 17, 0);
+                rule.step(&board, topology, &mut next);
+
+                for i in 0..board.cell_count() {
+                    let (x, y) = board.get_coord(i);
+                    let count: u32 = (0..size * size)
+                        .filter(|&k| {
+                            let (dx, dy) = ((k % size) as isize - r, (k / size) as isize - r);
+                            board
+                                .get(x + dx, y + dy, topology)
+                                .map(|cell| cell.is_alive())
+                                .unwrap_or(false)
+                        })
+                        .map(|k| weights[k])
+                        .sum();
+                    let expected = rule.next_state(board.cells[i], count);
+                    assert_eq!(next.cells[i], expected, "{} {:?}", rulestring, topology);
+                }
+                board = next;
+            }
+        }
+    }
+
+    let bosco = "R5,C0,M1,S34..58,B34..45,NM";
+    assert_eq!(LtlRule::parse(bosco).unwrap().to_string(), bosco);
+    assert!(LtlRule::parse("R1,C0,M0,S2..3,NM").is_err());
+    assert!(LtlRule::parse("R1,C0,M0,S2..3,B3,NW111").is_err());
+
+    // Conway's Life written as a Larger than Life rule matches the reference engine
+    let life = LtlRule::parse("R1,C0,M0,S2..3,B3..3,NM").unwrap();
+    let board = test_board(20, 20, 3);
+    let mut next = test_board(20, 20, 0);
+    life.step(&board, Topology::Torus, &mut next);
+    for i in 0..board.cell_count() {
+        let expected =
+            crate::get_next_cell_state(&board, &crate::Rule::default(), Topology::Torus, i);
+        assert_eq!(next.cells[i], expected);
+    }
+}
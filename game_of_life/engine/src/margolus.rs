@@ -0,0 +1,231 @@
+//! Block cellular automata on the Margolus neighborhood.  Rather than each cell looking at its
+//! neighbors, the board is partitioned into 2x2 blocks and every block is replaced as a whole
+//! according to a 16-entry table.  The partition is shifted by one cell diagonally every other
+//! generation so that information can flow between blocks.
+//!
+//! Blocks are numbered by adding up the bits of their live cells: 1 for the top left, 2 for the
+//! top right, 4 for the bottom left, and 8 for the bottom right.  Rulestrings use MCell's notation,
+//! `MS,D` followed by the new block for each of the 16 blocks in order, separated by semicolons.
+//!
+//! Only rules whose tables are permutations are accepted, which makes every one of them
+//! reversible: a generation can be undone exactly by running the inverse table over the same
+//! partition.
+
+use std::fmt;
+
+use crate::{topology::Topology, Board, Cell};
+
+const CRITTERS: [u8; 16] = [15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0];
+const TRON: [u8; 16] = [15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0];
+const BILLIARD_BALL_MACHINE: [u8; 16] = [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockRule {
+    /// New block for each block
+    table: [u8; 16],
+    /// Block that each block was before the rule was applied
+    inverse: [u8; 16],
+}
+
+/// Returns `true` if `rulestring` is a Margolus rulestring or the name of a known Margolus rule
+pub fn is_margolus_rulestring(rulestring: &str) -> bool {
+    let rulestring = rulestring.trim().to_ascii_lowercase();
+    rulestring.starts_with("ms,d") || BlockRule::named(&rulestring).is_some()
+}
+
+impl BlockRule {
+    /// Creates a rule from its table, which must contain each block exactly once
+    pub fn new(table: [u8; 16]) -> Result<Self, String> {
+        let mut inverse = [16u8; 16];
+        for (block, &new_block) in table.iter().enumerate() {
+            match inverse.get_mut(new_block as usize) {
+                Some(slot) if *slot == 16 => *slot = block as u8,
+                _ => {
+                    return Err(
+                        "Margolus rules must be reversible, so their tables must contain each \
+                         block from 0 to 15 exactly once"
+                            .to_owned(),
+                    )
+                }
+            }
+        }
+        Ok(BlockRule { table, inverse })
+    }
+
+    /// Returns the well-known rule with the given name, ignoring case and punctuation.  Known
+    /// rules are "Critters", "Tron", and "BBM" (the billiard ball machine).
+    pub fn named(name: &str) -> Option<Self> {
+        let normalized: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let table = match normalized.as_str() {
+            "critters" => CRITTERS,
+            "tron" => TRON,
+            "bbm" | "billiardballmachine" => BILLIARD_BALL_MACHINE,
+            _ => return None,
+        };
+        Some(BlockRule::new(table).unwrap())
+    }
+
+    /// Parses a rulestring like `MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0` or the name of a
+    /// well-known rule
+    pub fn parse(rulestring: &str) -> Result<Self, String> {
+        let rulestring = rulestring.trim();
+        if let Some(rule) = BlockRule::named(rulestring) {
+            return Ok(rule);
+        }
+
+        let invalid = || format!("Invalid Margolus rulestring: \"{}\"", rulestring);
+        if !rulestring.to_ascii_lowercase().starts_with("ms,d") {
+            return Err(invalid());
+        }
+        let entries = rulestring[4..]
+            .split(';')
+            .map(|entry| entry.trim().parse::<u8>())
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        if entries.len() != 16 {
+            return Err(format!(
+                "Margolus rules need an entry for each of the 16 blocks, but got {}",
+                entries.len()
+            ));
+        }
+
+        let mut table = [0u8; 16];
+        table.copy_from_slice(&entries);
+        BlockRule::new(table)
+    }
+
+    /// Advances `last` by a generation into `next`, which must be the same size.  The partition
+    /// used is picked by the parity of `generation`, the generation that `last` is at.
+    pub fn step(&self, last: &Board, topology: Topology, generation: u64, next: &mut Board) {
+        apply_blocks(&self.table, last, topology, generation, next);
+    }
+
+    /// Undoes a generation, turning the board that `step` produced from `generation` back into
+    /// the one that it was given
+    pub fn step_back(&self, last: &Board, topology: Topology, generation: u64, next: &mut Board) {
+        apply_blocks(&self.inverse, last, topology, generation, next);
+    }
+}
+
+/// Replaces every block of the partition used at `generation` according to `table`.  Blocks only
+/// wrap around the edges of a torus with an even number of cells along that axis; any other
+/// blocks that would hang off of the board are left as they are, which keeps every rule
+/// reversible regardless of the topology.
+fn apply_blocks(
+    table: &[u8; 16],
+    last: &Board,
+    topology: Topology,
+    generation: u64,
+    next: &mut Board,
+) {
+    let (width, height) = (last.width, last.height);
+    next.cells.copy_from_slice(&last.cells);
+
+    let offset = (generation % 2) as usize;
+    let wrapped = |coord: usize, size: usize| -> Option<usize> {
+        if coord < size {
+            Some(coord)
+        } else if topology == Topology::Torus && size % 2 == 0 {
+            Some(coord - size)
+        } else {
+            None
+        }
+    };
+
+    for y0 in (offset..height).step_by(2) {
+        let y1 = match wrapped(y0 + 1, height) {
+            Some(y1) => y1,
+            None => continue,
+        };
+        for x0 in (offset..width).step_by(2) {
+            let x1 = match wrapped(x0 + 1, width) {
+                Some(x1) => x1,
+                None => continue,
+            };
+
+            let indices = [
+                y0 * width + x0,
+                y0 * width + x1,
+                y1 * width + x0,
+                y1 * width + x1,
+            ];
+            let mut block = 0;
+            for (bit, &i) in indices.iter().enumerate() {
+                if last.cells[i].is_alive() {
+                    block |= 1 << bit;
+                }
+            }
+
+            let new_block = table[block];
+            for (bit, &i) in indices.iter().enumerate() {
+                next.cells[i] = if new_block & (1 << bit) != 0 {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                };
+            }
+        }
+    }
+}
+
+impl fmt::Display for BlockRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entries: Vec<String> = self.table.iter().map(u8::to_string).collect();
+        write!(f, "MS,D{}", entries.join(";"))
+    }
+}
+
+#[test]
+fn margolus_rules_are_reversible() {
+    let board = |width: usize, height: usize, alive: &[usize]| -> Board {
+        let mut cells = vec![Cell::Dead; width * height].into_boxed_slice();
+        for &i in alive {
+            cells[i] = Cell::Alive;
+        }
+        Board {
+            width,
+            height,
+            cells,
+        }
+    };
+
+    // A lone ball of the billiard ball machine moves diagonally through its block
+    let bbm = BlockRule::parse("BBM").unwrap();
+    let start = board(4, 4, &[5]);
+    let mut next = board(4, 4, &[]);
+    bbm.step(&start, Topology::Bounded, 0, &mut next);
+    assert_eq!(&*next.cells, &*board(4, 4, &[0]).cells);
+    bbm.step(&start, Topology::Bounded, 1, &mut next);
+    assert_eq!(&*next.cells, &*board(4, 4, &[10]).cells);
+
+    // Stepping forward then back restores the board exactly, including along odd edges
+    let critters = BlockRule::parse("critters").unwrap();
+    for &(topology, width, height) in &[
+        (Topology::Torus, 6, 4),
+        (Topology::Torus, 5, 3),
+        (Topology::Bounded, 5, 4),
+    ] {
+        let start = board(width, height, &[0, 1, 3, 6, 7, width * height - 1]);
+        let mut last = board(width, height, &[]);
+        last.cells.copy_from_slice(&start.cells);
+        let mut next = board(width, height, &[]);
+        for generation in 0..6 {
+            critters.step(&last, topology, generation, &mut next);
+            last.cells.copy_from_slice(&next.cells);
+        }
+        for generation in (0..6).rev() {
+            critters.step_back(&last, topology, generation, &mut next);
+            last.cells.copy_from_slice(&next.cells);
+        }
+        assert_eq!(&*last.cells, &*start.cells);
+    }
+
+    assert_eq!(BlockRule::parse(&critters.to_string()), Ok(critters));
+    assert!(BlockRule::parse("MS,D0;0;0;0;0;0;0;0;0;0;0;0;0;0;0;0").is_err());
+    assert!(BlockRule::parse("MS,D0;1;2").is_err());
+    assert!(is_margolus_rulestring("Tron") && !is_margolus_rulestring("B3/S23"));
+}
@@ -0,0 +1,139 @@
+//! The Life 1.06 coordinate list format: http://www.conwaylife.com/wiki/Life_1.06
+
+use std::fmt::Write;
+
+use super::{Pattern, PatternError};
+
+const HEADER: &str = "#Life 1.06";
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut name = None;
+    let mut comments = Vec::new();
+    let mut rule = None;
+    let mut coords: Vec<(isize, isize)> = Vec::new();
+    let mut seen_header = false;
+
+    for (line_ix, line) in text.lines().enumerate() {
+        let line_num = line_ix + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if !seen_header {
+            if line != HEADER {
+                return Err(PatternError::new(
+                    line_num,
+                    format!("Expected \"{}\" header", HEADER),
+                ));
+            }
+            seen_header = true;
+            continue;
+        }
+
+        if line.starts_with('#') {
+            let mut chars = line[1..].chars();
+            let kind = chars.next();
+            let content = chars.as_str().trim().to_owned();
+            match kind {
+                Some('D') | Some('C') if content.starts_with("Name:") => {
+                    name = Some(content["Name:".len()..].trim().to_owned())
+                }
+                Some('D') | Some('C') => comments.push(content),
+                Some('R') => rule = Some(content),
+                _ => (),
+            }
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let mut parse_coord = || -> Result<isize, PatternError> {
+            let part = parts
+                .next()
+                .ok_or_else(|| PatternError::new(line_num, "Expected an `x y` coordinate pair"))?;
+            part.parse().map_err(|_| {
+                PatternError::new(line_num, format!("Invalid coordinate \"{}\"", part))
+            })
+        };
+        let x = parse_coord()?;
+        let y = parse_coord()?;
+        if parts.next().is_some() {
+            return Err(PatternError::new(
+                line_num,
+                "Expected only two coordinates per line",
+            ));
+        }
+        coords.push((x, y));
+    }
+
+    if !seen_header {
+        return Err(PatternError::new(
+            1,
+            format!("Expected \"{}\" header", HEADER),
+        ));
+    }
+
+    if let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = (
+        coords.iter().map(|&(x, _)| x).min(),
+        coords.iter().map(|&(x, _)| x).max(),
+        coords.iter().map(|&(_, y)| y).min(),
+        coords.iter().map(|&(_, y)| y).max(),
+    ) {
+        let width = max_x.checked_sub(min_x).map(|span| span as usize + 1);
+        let height = max_y.checked_sub(min_y).map(|span| span as usize + 1);
+        PatternError::check_dimensions(
+            width.unwrap_or(usize::max_value()),
+            height.unwrap_or(usize::max_value()),
+            1,
+        )?;
+    }
+
+    let mut pattern = Pattern::from_coords(&coords);
+    pattern.name = name;
+    pattern.comments = comments;
+    pattern.rule = rule;
+    Ok(pattern)
+}
+
+/// Encodes the pattern's live cells with coordinates relative to its top-left corner.  The name
+/// and comments are written as `#D` lines, with the name prefixed by `Name:` as in plaintext
+/// files, and the rule as an `#R` line.
+pub fn encode(pattern: &Pattern) -> String {
+    let mut out = String::from(HEADER);
+    out.push('\n');
+    if let Some(ref name) = pattern.name {
+        writeln!(out, "#D Name: {}", name).unwrap();
+    }
+    for comment in &pattern.comments {
+        writeln!(out, "#D {}", comment).unwrap();
+    }
+    if let Some(ref rule) = pattern.rule {
+        writeln!(out, "#R {}", rule).unwrap();
+    }
+    for (x, y) in pattern.live_coords() {
+        writeln!(out, "{} {}", x, y).unwrap();
+    }
+    out
+}
+
+#[test]
+fn life106_round_trip() {
+    let pattern = parse("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+    assert_eq!(
+        pattern.live_coords(),
+        vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+    );
+    assert_eq!(encode(&pattern), "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n");
+
+    let text =
+        "#Life 1.06\n#D Name: Glider\n#D A small spaceship\n#R B3/S23\n1 0\n2 1\n0 2\n1 2\n2 2\n";
+    let pattern = parse(text).unwrap();
+    assert_eq!(pattern.name.as_ref().map(String::as_str), Some("Glider"));
+    assert_eq!(pattern.comments, vec!["A small spaceship".to_owned()]);
+    assert_eq!(pattern.rule.as_ref().map(String::as_str), Some("B3/S23"));
+    assert_eq!(encode(&pattern), text);
+
+    assert_eq!(parse("#Life 1.06\n0 0\n1 a\n").unwrap_err().line(), 3);
+    assert_eq!(parse("0 0\n").unwrap_err().line(), 1);
+    assert!(parse("#Life 1.06\n0 0\n100000000 100000000\n").is_err());
+}
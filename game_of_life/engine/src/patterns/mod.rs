@@ -0,0 +1,286 @@
+//! Reading and writing patterns in the text formats used by pattern collections like LifeWiki
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Board, Cell};
+
+pub mod life106;
+pub mod plaintext;
+pub mod rle;
+
+/// Patterns larger than this many cells are rejected rather than allocated
+const MAX_PATTERN_CELLS: usize = 1 << 26;
+
+/// A rectangular pattern that can be placed onto or extracted from a `Board`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+    /// Rulestring that the pattern was designed for, if one was provided
+    pub rule: Option<String>,
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Cell>,
+}
+
+/// How the cells of a stamped pattern are combined with the cells already on the board
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendMode {
+    /// Every cell of the pattern's bounding box replaces the cell beneath it
+    Overwrite,
+    /// Only the pattern's non-dead cells are written, leaving the rest of the board untouched
+    Or,
+    /// The pattern's non-dead cells toggle the cells beneath them
+    Xor,
+}
+
+impl BlendMode {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "overwrite" | "copy" => Ok(BlendMode::Overwrite),
+            "or" => Ok(BlendMode::Or),
+            "xor" => Ok(BlendMode::Xor),
+            _ => Err(format!("Unknown blend mode: \"{}\"", name)),
+        }
+    }
+
+    /// Returns the cell that results from stamping `incoming` over `existing`
+    #[inline]
+    pub fn blend(self, existing: Cell, incoming: Cell) -> Cell {
+        match (self, incoming) {
+            (BlendMode::Overwrite, _) => incoming,
+            (_, Cell::Dead) => existing,
+            (BlendMode::Or, _) => incoming,
+            (BlendMode::Xor, _) if existing == Cell::Dead => incoming,
+            (BlendMode::Xor, _) => Cell::Dead,
+        }
+    }
+}
+
+/// Returned to the JS as the error value when a pattern fails to parse or the arguments passed
+/// along with it are invalid
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternError {
+    line: usize,
+    message: String,
+}
+
+impl PatternError {
+    pub fn new<S: Into<String>>(line: usize, message: S) -> Self {
+        PatternError {
+            line,
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error for an invalid argument passed along with a pattern, which isn't tied to any
+    /// line of it
+    pub fn invalid_argument<S: Into<String>>(message: S) -> Self {
+        PatternError::new(0, message)
+    }
+
+    /// Makes sure that a pattern with the given dimensions is small enough to be allocated
+    pub fn check_dimensions(width: usize, height: usize, line: usize) -> Result<(), Self> {
+        match width.checked_mul(height) {
+            Some(cell_count) if cell_count <= MAX_PATTERN_CELLS => Ok(()),
+            _ => Err(PatternError::new(
+                line,
+                format!("Pattern dimensions of {}x{} are too large", width, height),
+            )),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl PatternError {
+    /// 1-indexed line of the input on which the error occurred, or 0 if it was caused by an
+    /// invalid argument rather than the pattern itself
+    #[wasm_bindgen(getter)]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "Invalid argument: {}", self.message);
+        }
+        write!(
+            f,
+            "Error parsing pattern on line {}: {}",
+            self.line, self.message
+        )
+    }
+}
+
+impl Pattern {
+    pub fn new(width: usize, height: usize) -> Self {
+        Pattern {
+            name: None,
+            comments: Vec::new(),
+            rule: None,
+            width,
+            height,
+            cells: vec![Cell::Dead; width * height],
+        }
+    }
+
+    /// Builds a pattern from the given live cell coordinates, shifting them so that the top-left
+    /// of their bounding box lies at (0, 0).
+    pub fn from_coords(coords: &[(isize, isize)]) -> Self {
+        if coords.is_empty() {
+            return Pattern::new(0, 0);
+        }
+
+        let min_x = coords.iter().map(|&(x, _)| x).min().unwrap();
+        let max_x = coords.iter().map(|&(x, _)| x).max().unwrap();
+        let min_y = coords.iter().map(|&(_, y)| y).min().unwrap();
+        let max_y = coords.iter().map(|&(_, y)| y).max().unwrap();
+
+        let mut pattern = Pattern::new((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+        for &(x, y) in coords {
+            pattern.set((x - min_x) as usize, (y - min_y) as usize, Cell::Alive);
+        }
+        pattern
+    }
+
+    /// Extracts the bounding box of all live and dying cells on the board
+    pub fn from_board(board: &Board) -> Self {
+        let coords: Vec<(isize, isize)> = board
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell != Cell::Dead)
+            .map(|(i, _)| board.get_coord(i))
+            .collect();
+        let mut pattern = Pattern::from_coords(&coords);
+
+        // `from_coords` marks every cell as alive, so copy over the real states
+        if let (Some(min_x), Some(min_y)) = (
+            coords.iter().map(|&(x, _)| x).min(),
+            coords.iter().map(|&(_, y)| y).min(),
+        ) {
+            for &(x, y) in &coords {
+                let cell = board.cells[y as usize * board.width + x as usize];
+                pattern.set((x - min_x) as usize, (y - min_y) as usize, cell);
+            }
+        }
+        pattern
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[y * self.width + x]
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        self.cells[y * self.width + x] = cell;
+    }
+
+    /// Returns a copy of the pattern that is first mirrored left-to-right if `flip` is set and
+    /// then rotated clockwise by `quarter_turns` 90 degree turns
+    pub fn transformed(&self, quarter_turns: u32, flip: bool) -> Self {
+        let quarter_turns = quarter_turns % 4;
+        let (width, height) = if quarter_turns % 2 == 0 {
+            (self.width, self.height)
+        } else {
+            (self.height, self.width)
+        };
+
+        let mut transformed = Pattern {
+            cells: vec![Cell::Dead; width * height],
+            width,
+            height,
+            ..self.clone()
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let src_x = if flip { self.width - 1 - x } else { x };
+                let (dst_x, dst_y) = match quarter_turns {
+                    0 => (x, y),
+                    1 => (self.height - 1 - y, x),
+                    2 => (self.width - 1 - x, self.height - 1 - y),
+                    _ => (y, self.width - 1 - x),
+                };
+                transformed.set(dst_x, dst_y, self.get(src_x, y));
+            }
+        }
+        transformed
+    }
+
+    /// Returns the coordinates of all live cells in the pattern
+    pub fn live_coords(&self) -> Vec<(usize, usize)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.is_alive())
+            .map(|(i, _)| (i % self.width, i / self.width))
+            .collect()
+    }
+}
+
+/// Parses a pattern in any of the supported formats, picking the format from its header
+pub fn parse_any(text: &str) -> Result<Pattern, PatternError> {
+    let first_line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("");
+    let is_rle_header = |line: &str| line.starts_with('x') && line.contains('=');
+
+    if first_line.starts_with("#Life 1.06") {
+        life106::parse(text)
+    } else if first_line.starts_with('#') || is_rle_header(first_line) {
+        rle::parse(text)
+    } else {
+        plaintext::parse(text)
+    }
+}
+
+#[test]
+fn transforms_and_blending() {
+    // The glider from `plaintext_round_trip`, heading down and to the right
+    let glider = parse_any(".O.\n..O\nOOO\n").unwrap();
+    let coords = |pattern: &Pattern| {
+        let mut coords = pattern.live_coords();
+        coords.sort();
+        coords
+    };
+
+    // A clockwise turn makes it head down and to the left
+    assert_eq!(
+        coords(&glider.transformed(1, false)),
+        vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 1)]
+    );
+    // Mirroring also makes it head down and to the left but in a different phase, and four turns
+    // do nothing at all
+    assert_eq!(
+        coords(&glider.transformed(0, true)),
+        vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 2)]
+    );
+    assert_eq!(glider.transformed(4, false), glider);
+
+    let mut wide = Pattern::new(3, 1);
+    wide.set(0, 0, Cell::Alive);
+    let tall = wide.transformed(3, false);
+    assert_eq!((tall.width, tall.height), (1, 3));
+    assert_eq!(tall.live_coords(), vec![(0, 2)]);
+
+    assert_eq!(BlendMode::Or.blend(Cell::Alive, Cell::Dead), Cell::Alive);
+    assert_eq!(
+        BlendMode::Overwrite.blend(Cell::Alive, Cell::Dead),
+        Cell::Dead
+    );
+    assert_eq!(BlendMode::Xor.blend(Cell::Alive, Cell::Alive), Cell::Dead);
+    assert_eq!(BlendMode::Xor.blend(Cell::Dead, Cell::Alive), Cell::Alive);
+}
@@ -0,0 +1,98 @@
+//! The plaintext `.cells` format: http://www.conwaylife.com/wiki/Plaintext
+
+use std::fmt::Write;
+
+use super::{Pattern, PatternError};
+use crate::Cell;
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut name = None;
+    let mut comments = Vec::new();
+    let mut rows: Vec<Vec<Cell>> = Vec::new();
+
+    for (line_ix, line) in text.lines().enumerate() {
+        let line_num = line_ix + 1;
+        let line = line.trim_end();
+
+        if line.starts_with('!') {
+            let content = line[1..].trim();
+            if content.starts_with("Name:") {
+                name = Some(content["Name:".len()..].trim().to_owned());
+            } else {
+                comments.push(content.to_owned());
+            }
+            continue;
+        }
+
+        let row = line
+            .chars()
+            .map(|c| match c {
+                '.' => Ok(Cell::Dead),
+                'O' | '*' => Ok(Cell::Alive),
+                c => Err(PatternError::new(
+                    line_num,
+                    format!("Unexpected character '{}' in pattern", c),
+                )),
+            })
+            .collect::<Result<Vec<Cell>, PatternError>>()?;
+        rows.push(row);
+    }
+
+    // Trailing blank lines aren't part of the pattern
+    while rows.last().map(Vec::is_empty).unwrap_or(false) {
+        rows.pop();
+    }
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    PatternError::check_dimensions(width, rows.len(), 1)?;
+    let mut pattern = Pattern::new(width, rows.len());
+    pattern.name = name;
+    pattern.comments = comments;
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, cell) in row.into_iter().enumerate() {
+            pattern.set(x, y, cell);
+        }
+    }
+    Ok(pattern)
+}
+
+pub fn encode(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some(ref name) = pattern.name {
+        writeln!(out, "!Name: {}", name).unwrap();
+    }
+    for comment in &pattern.comments {
+        writeln!(out, "!{}", comment).unwrap();
+    }
+
+    for y in 0..pattern.height {
+        for x in 0..pattern.width {
+            out.push(if pattern.get(x, y).is_alive() {
+                'O'
+            } else {
+                '.'
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[test]
+fn plaintext_round_trip() {
+    let glider = "!Name: Glider\n!A small spaceship\n.O.\n..O\nOOO\n";
+    let pattern = parse(glider).unwrap();
+    assert_eq!(pattern.name.as_ref().map(String::as_str), Some("Glider"));
+    assert_eq!(
+        pattern.live_coords(),
+        vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+    );
+    assert_eq!(encode(&pattern), glider);
+
+    // Rows may omit trailing dead cells and blank lines are empty rows
+    let ragged = parse("O\n\n..O\n").unwrap();
+    assert_eq!((ragged.width, ragged.height), (3, 3));
+    assert_eq!(ragged.live_coords(), vec![(0, 0), (2, 2)]);
+
+    assert_eq!(parse(".O.\n.X.\n").unwrap_err().line(), 2);
+}
@@ -0,0 +1,338 @@
+//! The Run Length Encoded format: http://www.conwaylife.com/wiki/Run_Length_Encoded
+//!
+//! Patterns for multi-state rules use Golly's extended tags: `.` for dead cells and `A` through
+//! `X` for states 1 through 24, with a prefix of `p` through `y` for each further block of 24.
+
+use std::fmt::Write;
+
+use super::{Pattern, PatternError};
+use crate::Cell;
+
+/// Lines of the encoded pattern body are wrapped to stay under this length
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Converts a multi-state tag (`A`, `B`, ..., with an optional `p`-`y` prefix) to a cell
+fn parse_state_tag(prefix: Option<char>, c: char) -> Option<Cell> {
+    let block = match prefix {
+        Some(prefix) => prefix as u32 - 'p' as u32 + 1,
+        None => 0,
+    };
+    let state = block * 24 + (c as u32 - 'A' as u32) + 1;
+    match state {
+        1 => Some(Cell::Alive),
+        2..=255 => Some(Cell::Dying(state as u8)),
+        _ => None,
+    }
+}
+
+/// Returns the multi-state tag for a cell, which is the inverse of `parse_state_tag`
+fn state_tag(cell: Cell) -> String {
+    let state = cell.state_index() as u32;
+    if state == 0 {
+        return ".".to_owned();
+    }
+
+    let mut tag = String::new();
+    let block = (state - 1) / 24;
+    if block > 0 {
+        tag.push((b'p' + block as u8 - 1) as char);
+    }
+    tag.push((b'A' + ((state - 1) % 24) as u8) as char);
+    tag
+}
+
+/// Parses the `x = 3, y = 3, rule = B3/S23` header line, returning the width, height, and rule
+fn parse_header(
+    line: &str,
+    line_num: usize,
+) -> Result<(usize, usize, Option<String>), PatternError> {
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+
+    let mut last_key = "";
+    for field in line.split(',') {
+        let mut parts = field.splitn(2, '=');
+        let key = parts.next().unwrap().trim();
+        let value = match parts.next() {
+            Some(value) => value.trim(),
+            // Larger than Life rulestrings contain commas of their own
+            None if last_key == "rule" => {
+                let rule: &mut String = rule.as_mut().unwrap();
+                rule.push(',');
+                rule.push_str(key);
+                continue;
+            }
+            None => {
+                return Err(PatternError::new(
+                    line_num,
+                    format!("Invalid header field \"{}\"", field),
+                ))
+            }
+        };
+        let parse_dimension = |value: &str| {
+            value.parse::<usize>().map_err(|_| {
+                PatternError::new(line_num, format!("Invalid dimension \"{}\"", value))
+            })
+        };
+
+        match key {
+            "x" => width = Some(parse_dimension(value)?),
+            "y" => height = Some(parse_dimension(value)?),
+            "rule" => rule = Some(value.to_owned()),
+            _ => warn!("Ignoring unknown RLE header field \"{}\"", key),
+        }
+        last_key = key;
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(PatternError::new(
+            line_num,
+            "Header is missing the `x` or `y` field",
+        )),
+    }
+}
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut name = None;
+    let mut comments = Vec::new();
+    let mut header_rule = None;
+    let mut pattern: Option<Pattern> = None;
+
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut run_count: Option<usize> = None;
+    let mut state_prefix: Option<char> = None;
+    let mut done = false;
+
+    for (line_ix, line) in text.lines().enumerate() {
+        let line_num = line_ix + 1;
+        let line = line.trim();
+        if line.is_empty() || done {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            let mut chars = line[1..].chars();
+            let kind = chars.next();
+            let content = chars.as_str().trim().to_owned();
+            match kind {
+                Some('N') => name = Some(content),
+                Some('C') | Some('c') => comments.push(content),
+                Some('r') => header_rule = Some(content),
+                _ => (),
+            }
+            continue;
+        }
+
+        let pattern = match pattern {
+            Some(ref mut pattern) => pattern,
+            None => {
+                let (width, height, rule) = parse_header(line, line_num)?;
+                PatternError::check_dimensions(width, height, line_num)?;
+                let mut new_pattern = Pattern::new(width, height);
+                new_pattern.rule = rule.or_else(|| header_rule.take());
+                pattern = Some(new_pattern);
+                continue;
+            }
+        };
+
+        let too_large = || PatternError::new(line_num, "Run count is too large");
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                let count = run_count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit as usize))
+                    .ok_or_else(too_large)?;
+                run_count = Some(count);
+                continue;
+            }
+
+            if let 'p'..='y' = c {
+                if state_prefix.is_some() {
+                    return Err(PatternError::new(
+                        line_num,
+                        format!("Unexpected character '{}' in pattern", c),
+                    ));
+                }
+                state_prefix = Some(c);
+                continue;
+            }
+
+            let count = run_count.take().unwrap_or(1);
+            let prefix = state_prefix.take();
+            let cell = match c {
+                'o' if prefix.is_none() => Some(Cell::Alive),
+                'A'..='X' => parse_state_tag(prefix, c),
+                _ => None,
+            };
+            if let (Some(prefix), None) = (prefix, cell) {
+                return Err(PatternError::new(
+                    line_num,
+                    format!("Invalid multi-state tag \"{}{}\"", prefix, c),
+                ));
+            }
+
+            match c {
+                'b' | '.' => x = x.checked_add(count).ok_or_else(too_large)?,
+                _ if cell.is_some() => {
+                    let end = x.checked_add(count).ok_or_else(too_large)?;
+                    if end > pattern.width || y >= pattern.height {
+                        return Err(PatternError::new(
+                            line_num,
+                            "Pattern extends past the dimensions given in its header",
+                        ));
+                    }
+                    for _ in 0..count {
+                        pattern.set(x, y, cell.unwrap());
+                        x += 1;
+                    }
+                }
+                '$' => {
+                    x = 0;
+                    y = y.checked_add(count).ok_or_else(too_large)?;
+                }
+                '!' => {
+                    done = true;
+                    break;
+                }
+                c if c.is_whitespace() => (),
+                c => {
+                    return Err(PatternError::new(
+                        line_num,
+                        format!("Unexpected character '{}' in pattern", c),
+                    ))
+                }
+            }
+        }
+    }
+
+    match pattern {
+        Some(mut pattern) => {
+            pattern.name = name;
+            pattern.comments = comments;
+            Ok(pattern)
+        }
+        None => Err(PatternError::new(
+            text.lines().count().max(1),
+            "No `x = ..., y = ...` header line found",
+        )),
+    }
+}
+
+/// Appends a single run to the output, wrapping lines as needed
+fn push_run(out: &mut String, line_len: &mut usize, count: usize, tag: &str) {
+    if count == 0 {
+        return;
+    }
+
+    let run = if count == 1 {
+        tag.to_owned()
+    } else {
+        format!("{}{}", count, tag)
+    };
+    if *line_len + run.len() > MAX_LINE_LENGTH {
+        out.push('\n');
+        *line_len = 0;
+    }
+    *line_len += run.len();
+    out.push_str(&run);
+}
+
+pub fn encode(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some(ref name) = pattern.name {
+        writeln!(out, "#N {}", name).unwrap();
+    }
+    for comment in &pattern.comments {
+        writeln!(out, "#C {}", comment).unwrap();
+    }
+    write!(out, "x = {}, y = {}", pattern.width, pattern.height).unwrap();
+    if let Some(ref rule) = pattern.rule {
+        write!(out, ", rule = {}", rule).unwrap();
+    }
+    out.push('\n');
+
+    // Two-state patterns use the classic `b`/`o` tags
+    let multi_state = pattern.cells.iter().any(|cell| match cell {
+        Cell::Dying(_) => true,
+        _ => false,
+    });
+    let tag_for = |cell: Cell| match cell {
+        _ if multi_state => state_tag(cell),
+        Cell::Alive => "o".to_owned(),
+        _ => "b".to_owned(),
+    };
+
+    let mut line_len = 0;
+    // Row ends are deferred so that trailing empty rows can be collapsed into a single run
+    let mut pending_row_ends = 0;
+    for y in 0..pattern.height {
+        let mut run_cell = None;
+        let mut run_count = 0;
+        for x in 0..pattern.width {
+            let cell = pattern.get(x, y);
+            if run_cell == Some(cell) {
+                run_count += 1;
+                continue;
+            }
+
+            if cell != Cell::Dead {
+                push_run(&mut out, &mut line_len, pending_row_ends, "$");
+                pending_row_ends = 0;
+            }
+            if let Some(prev_cell) = run_cell {
+                push_run(&mut out, &mut line_len, run_count, &tag_for(prev_cell));
+            }
+            run_cell = Some(cell);
+            run_count = 1;
+        }
+        // Trailing dead cells in a row are implied and never written
+        match run_cell {
+            Some(Cell::Dead) | None => (),
+            Some(cell) => push_run(&mut out, &mut line_len, run_count, &tag_for(cell)),
+        }
+        pending_row_ends += 1;
+    }
+    push_run(&mut out, &mut line_len, 1, "!");
+    out.push('\n');
+    out
+}
+
+#[test]
+fn rle_round_trip() {
+    let glider = "#N Glider\n#C A small spaceship\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+    let pattern = parse(glider).unwrap();
+    assert_eq!(pattern.name.as_ref().map(String::as_str), Some("Glider"));
+    assert_eq!(pattern.rule.as_ref().map(String::as_str), Some("B3/S23"));
+    assert_eq!(
+        pattern.live_coords(),
+        vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+    );
+    assert_eq!(encode(&pattern), glider);
+
+    let with_gap = parse("x = 2, y = 4\no$$$bo!").unwrap();
+    assert_eq!(with_gap.live_coords(), vec![(0, 0), (1, 3)]);
+    assert_eq!(encode(&with_gap), "x = 2, y = 4\no3$bo!\n");
+
+    let brians_brain = parse("x = 3, y = 2, rule = B2/S/C3\nA.B$pA2A!").unwrap();
+    assert_eq!(brians_brain.get(2, 0), Cell::Dying(2));
+    assert_eq!(brians_brain.get(0, 1), Cell::Dying(25));
+    assert_eq!(
+        encode(&brians_brain),
+        "x = 3, y = 2, rule = B2/S/C3\nA.B$pA2A!\n"
+    );
+
+    let bosco = parse("x = 1, y = 1, rule = R5,C0,M1,S34..58,B34..45,NM\no!").unwrap();
+    assert_eq!(
+        bosco.rule.as_ref().map(String::as_str),
+        Some("R5,C0,M1,S34..58,B34..45,NM")
+    );
+
+    assert_eq!(parse("x = 1, y = 1\n2o!").unwrap_err().line(), 2);
+    assert!(parse("x = 1, y = 1\n99999999999999999999o!").is_err());
+    assert!(parse("x = 1, y = 1\n18446744073709551615bo!").is_err());
+    assert!(parse("x = 1, y = 1\n18446744073709551615$2$o!").is_err());
+    assert!(parse("bo$2bo$3o!").is_err());
+}
@@ -0,0 +1,226 @@
+//! Alternative ways of coloring the board that depend on the history of each cell rather than
+//! just its current state.  While one of them is active, the age of every cell and the number of
+//! generations that it has spent alive are tracked alongside the board.
+
+use crate::Cell;
+
+/// Generations over which the age and fade gradients are spread by default
+const DEFAULT_SPAN: u32 = 64;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RenderMode {
+    /// Each state is drawn with its color from the palette
+    States,
+    /// Live cells are colored by how many generations they've been alive
+    Age,
+    /// Dead cells fade out over several generations after dying
+    Fade,
+    /// Every cell is colored by the total number of generations it has spent alive
+    Heatmap,
+}
+
+impl RenderMode {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "states" | "state" => Ok(RenderMode::States),
+            "age" => Ok(RenderMode::Age),
+            "fade" => Ok(RenderMode::Fade),
+            "heatmap" | "ever_alive" => Ok(RenderMode::Heatmap),
+            _ => Err(format!("Unknown render mode: \"{}\"", name)),
+        }
+    }
+}
+
+/// Colors evenly spaced from 0 to 1 that are linearly interpolated between
+#[derive(Clone, PartialEq, Debug)]
+pub struct Gradient {
+    stops: Vec<[u8; 3]>,
+}
+
+impl Gradient {
+    pub fn new(stops: Vec<[u8; 3]>) -> Self {
+        Gradient { stops }
+    }
+
+    /// Builds a gradient from a flat array of RGB triples.  At least one color is required.
+    pub fn from_rgb(rgb: &[u8]) -> Result<Self, String> {
+        if rgb.len() < 3 || rgb.len() % 3 != 0 {
+            return Err(format!(
+                "Gradients must be given as RGB triples, but {} values were provided",
+                rgb.len()
+            ));
+        }
+
+        let stops = rgb
+            .chunks(3)
+            .map(|color| [color[0], color[1], color[2]])
+            .collect();
+        Ok(Gradient { stops })
+    }
+
+    pub fn sample(&self, t: f32) -> [u8; 3] {
+        let position = t.max(0.).min(1.) * (self.stops.len() - 1) as f32;
+        let ix = position.floor() as usize;
+        if ix + 1 >= self.stops.len() {
+            return self.stops[self.stops.len() - 1];
+        }
+
+        let fraction = position - ix as f32;
+        let (from, to) = (self.stops[ix], self.stops[ix + 1]);
+        let mut color = [0u8; 3];
+        for channel in 0..3 {
+            color[channel] = (from[channel] as f32
+                + (to[channel] as f32 - from[channel] as f32) * fraction)
+                .round() as u8;
+        }
+        color
+    }
+}
+
+/// Per-cell history that the age-based render modes are drawn from
+pub struct CellAges {
+    was_alive: Vec<bool>,
+    /// Generations since each cell last started or stopped being alive
+    pub ages: Vec<u32>,
+    /// Total generations that each cell has spent alive
+    pub ever_alive: Vec<u32>,
+    max_ever_alive: u32,
+}
+
+impl CellAges {
+    pub fn new(cells: &[Cell]) -> Self {
+        let was_alive: Vec<bool> = cells.iter().map(Cell::is_alive).collect();
+        let ever_alive: Vec<u32> = was_alive.iter().map(|&alive| alive as u32).collect();
+        CellAges {
+            ages: vec![0; cells.len()],
+            max_ever_alive: ever_alive.iter().cloned().max().unwrap_or(0),
+            ever_alive,
+            was_alive,
+        }
+    }
+
+    /// Ages every cell by a generation, restarting the age of cells that were born or died
+    pub fn advance(&mut self, cells: &[Cell]) {
+        for (i, cell) in cells.iter().enumerate() {
+            let alive = cell.is_alive();
+            if alive != self.was_alive[i] {
+                self.was_alive[i] = alive;
+                self.ages[i] = 0;
+            } else {
+                self.ages[i] = self.ages[i].saturating_add(1);
+            }
+
+            if alive {
+                self.ever_alive[i] = self.ever_alive[i].saturating_add(1);
+                self.max_ever_alive = self.max_ever_alive.max(self.ever_alive[i]);
+            }
+        }
+    }
+
+    /// Records a cell that was changed by something other than the simulation
+    pub fn reset_cell(&mut self, i: usize, cell: Cell) {
+        if cell.is_alive() != self.was_alive[i] {
+            self.was_alive[i] = cell.is_alive();
+            self.ages[i] = 0;
+        }
+    }
+}
+
+pub struct Renderer {
+    pub mode: RenderMode,
+    pub age_gradient: Gradient,
+    pub fade_gradient: Gradient,
+    pub heatmap_gradient: Gradient,
+    /// Number of generations over which the age and fade gradients are spread
+    pub span: u32,
+    /// Only tracked while a mode other than `States` is active
+    pub ages: Option<CellAges>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer {
+            mode: RenderMode::States,
+            age_gradient: Gradient::new(vec![
+                [255, 255, 255],
+                [255, 220, 0],
+                [255, 80, 0],
+                [160, 0, 0],
+            ]),
+            fade_gradient: Gradient::new(vec![[96, 96, 192], [0, 0, 0]]),
+            heatmap_gradient: Gradient::new(vec![
+                [0, 0, 0],
+                [128, 0, 0],
+                [255, 128, 0],
+                [255, 255, 160],
+            ]),
+            span: DEFAULT_SPAN,
+            ages: None,
+        }
+    }
+}
+
+impl Renderer {
+    pub fn set_mode(&mut self, mode: RenderMode, cells: &[Cell]) {
+        self.mode = mode;
+        self.ages = match mode {
+            RenderMode::States => None,
+            _ => Some(CellAges::new(cells)),
+        };
+    }
+
+    pub fn gradient_mut(&mut self, mode: RenderMode) -> Option<&mut Gradient> {
+        match mode {
+            RenderMode::States => None,
+            RenderMode::Age => Some(&mut self.age_gradient),
+            RenderMode::Fade => Some(&mut self.fade_gradient),
+            RenderMode::Heatmap => Some(&mut self.heatmap_gradient),
+        }
+    }
+
+    /// Returns the color to draw cell `i` with, given its color from the palette
+    pub fn color(&self, i: usize, cell: Cell, palette_color: [u8; 3]) -> [u8; 3] {
+        let ages = match self.ages {
+            Some(ref ages) => ages,
+            None => return palette_color,
+        };
+        let span_fraction = |age: u32| age as f32 / self.span.max(1) as f32;
+
+        match self.mode {
+            RenderMode::States => palette_color,
+            RenderMode::Age if cell.is_alive() => {
+                self.age_gradient.sample(span_fraction(ages.ages[i]))
+            }
+            RenderMode::Fade if cell == Cell::Dead && ages.ages[i] < self.span => {
+                // Cells that have never been alive have nothing to fade out from
+                if ages.ever_alive[i] == 0 {
+                    palette_color
+                } else {
+                    self.fade_gradient.sample(span_fraction(ages.ages[i]))
+                }
+            }
+            RenderMode::Age | RenderMode::Fade => palette_color,
+            RenderMode::Heatmap => {
+                // Log scale so that a few long-lived still lifes don't wash everything else out
+                let t = (ages.ever_alive[i] as f32).ln_1p() / (ages.max_ever_alive as f32).ln_1p();
+                self.heatmap_gradient
+                    .sample(if t.is_nan() { 0. } else { t })
+            }
+        }
+    }
+}
+
+#[test]
+fn ages_and_gradients() {
+    let gradient = Gradient::from_rgb(&[0, 0, 0, 200, 100, 50]).unwrap();
+    assert_eq!(gradient.sample(0.), [0, 0, 0]);
+    assert_eq!(gradient.sample(0.5), [100, 50, 25]);
+    assert_eq!(gradient.sample(2.), [200, 100, 50]);
+    assert!(Gradient::from_rgb(&[1, 2]).is_err());
+
+    let mut ages = CellAges::new(&[Cell::Alive, Cell::Dead]);
+    ages.advance(&[Cell::Alive, Cell::Alive]);
+    ages.advance(&[Cell::Dead, Cell::Alive]);
+    assert_eq!(ages.ages, vec![0, 1]);
+    assert_eq!(ages.ever_alive, vec![2, 2]);
+}
@@ -0,0 +1,233 @@
+//! Parsing for Life-like rulestrings in B/S notation (`B3/S23`) as well as the older S/B notation
+//! (`23/3`) used by many pattern collections.  Both notations accept a third section giving the
+//! number of states for Generations rules, e.g. `B2/S/C3` or `/2/3` for Brian's Brain.  A
+//! trailing `H` or `L` selects the hexagonal or triangular grid, as in `B2/S34H`.
+
+use std::fmt;
+
+use crate::{
+    grid::{Grid, MAX_NEIGHBORS},
+    Cell,
+};
+
+/// Birth/survival lookup tables indexed by the number of live neighbors a cell has
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rule {
+    pub birth: [bool; MAX_NEIGHBORS + 1],
+    pub survival: [bool; MAX_NEIGHBORS + 1],
+    /// Total number of cell states including dead and alive.  Live cells that don't survive pass
+    /// through `states - 2` dying states, which neither count as live neighbors nor can be born
+    /// into, before becoming dead.  Always 2 for Life-like rules.
+    pub states: u8,
+    /// Grid that the rule's neighbor counts apply to
+    pub grid: Grid,
+}
+
+impl Default for Rule {
+    /// Conway's Game of Life, B3/S23
+    fn default() -> Self {
+        let mut rule = Rule {
+            birth: [false; MAX_NEIGHBORS + 1],
+            survival: [false; MAX_NEIGHBORS + 1],
+            states: 2,
+            grid: Grid::Square,
+        };
+        rule.birth[3] = true;
+        rule.survival[2] = true;
+        rule.survival[3] = true;
+        rule
+    }
+}
+
+fn parse_counts(
+    counts: &str,
+    grid: Grid,
+    table: &mut [bool; MAX_NEIGHBORS + 1],
+) -> Result<(), String> {
+    for c in counts.chars() {
+        match c.to_digit(10) {
+            Some(count) if count as usize <= grid.neighbor_count() => table[count as usize] = true,
+            _ => return Err(format!("Invalid neighbor count '{}' in rulestring", c)),
+        }
+    }
+    Ok(())
+}
+
+fn parse_state_count(states: &str) -> Result<u8, String> {
+    match states.parse::<u8>() {
+        Ok(states) if states >= 2 => Ok(states),
+        _ => Err(format!(
+            "Invalid state count \"{}\" in rulestring; expected a number from 2 to 255",
+            states
+        )),
+    }
+}
+
+impl Rule {
+    /// Parses a rulestring like `B36/S23`, `b3s23`, `S23/B3`, or `23/3` (survival first).
+    /// Generations rules add the number of states as a third section: `B2/S/C3`, `B2SC3`, or
+    /// `/2/3`.  Rules for the hexagonal grid end with `H` and rules for the triangular grid end
+    /// with `L`.  Neighbor counts are single digits, so triangular counts of 10 and above can't be
+    /// expressed.
+    pub fn parse(rulestring: &str) -> Result<Self, String> {
+        let rulestring: String = rulestring.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut rule = Rule {
+            birth: [false; MAX_NEIGHBORS + 1],
+            survival: [false; MAX_NEIGHBORS + 1],
+            states: 2,
+            grid: Grid::Square,
+        };
+
+        let mut lower = rulestring.to_ascii_lowercase();
+        rule.grid = match lower.chars().last() {
+            Some('h') => Grid::Hexagonal,
+            Some('l') => Grid::Triangular,
+            _ => Grid::Square,
+        };
+        if rule.grid != Grid::Square {
+            lower.pop();
+        }
+        let grid = rule.grid;
+
+        if lower.starts_with('b') || lower.starts_with('s') {
+            let mut seen_birth = false;
+            let mut seen_survival = false;
+            let mut seen_states = false;
+            // Split into `B...`, `S...`, and optional `C...` sections, with or without a
+            // separating slash
+            let mut sections: Vec<&str> = Vec::new();
+            let mut section_start = 0;
+            for (i, c) in lower.char_indices().skip(1) {
+                if c == 'b' || c == 's' || c == 'c' {
+                    sections.push(&lower[section_start..i]);
+                    section_start = i;
+                }
+            }
+            sections.push(&lower[section_start..]);
+
+            for section in sections {
+                let section = section.trim_end_matches('/');
+                let (kind, counts) = section.split_at(1);
+                match kind {
+                    "b" if !seen_birth => {
+                        seen_birth = true;
+                        parse_counts(counts, grid, &mut rule.birth)?;
+                    }
+                    "s" if !seen_survival => {
+                        seen_survival = true;
+                        parse_counts(counts, grid, &mut rule.survival)?;
+                    }
+                    "c" if !seen_states => {
+                        seen_states = true;
+                        rule.states = parse_state_count(counts)?;
+                    }
+                    _ => return Err(format!("Invalid rulestring: \"{}\"", rulestring)),
+                }
+            }
+
+            if !seen_birth || !seen_survival {
+                return Err(format!(
+                    "Rulestring \"{}\" must contain both a B and an S section",
+                    rulestring
+                ));
+            }
+            return Ok(rule);
+        }
+
+        // Traditional `survival/birth` or `survival/birth/states` notation
+        let parts: Vec<&str> = lower.split('/').collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(format!("Invalid rulestring: \"{}\"", rulestring));
+        }
+        parse_counts(parts[0], grid, &mut rule.survival)?;
+        parse_counts(parts[1], grid, &mut rule.birth)?;
+        if let Some(states) = parts.get(2) {
+            rule.states = parse_state_count(states)?;
+        }
+        Ok(rule)
+    }
+
+    /// Returns `true` if this is a two-state rule on the square grid that can be run by the
+    /// bit-packed and unbounded engines
+    #[inline]
+    pub fn is_life_like(&self) -> bool {
+        self.states == 2 && self.grid == Grid::Square
+    }
+
+    /// Returns the state that a live cell that fails to survive moves into
+    #[inline]
+    pub fn first_dying_state(&self) -> Cell {
+        if self.states > 2 {
+            Cell::Dying(2)
+        } else {
+            Cell::Dead
+        }
+    }
+
+    /// Returns the state following the dying state `state`
+    #[inline]
+    pub fn next_dying_state(&self, state: u8) -> Cell {
+        if state + 1 < self.states {
+            Cell::Dying(state + 1)
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    /// Formats the rule in canonical B/S notation, e.g. `B36/S23`, `B2/S/C3`, or `B2/S34H`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for (count, _) in self.birth.iter().enumerate().filter(|(_, born)| **born) {
+            write!(f, "{}", count)?;
+        }
+        write!(f, "/S")?;
+        for (count, _) in self
+            .survival
+            .iter()
+            .enumerate()
+            .filter(|(_, survives)| **survives)
+        {
+            write!(f, "{}", count)?;
+        }
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        write!(f, "{}", self.grid.rule_suffix())
+    }
+}
+
+#[test]
+fn rulestring_notations() {
+    let highlife = Rule::parse("B36/S23").unwrap();
+    assert_eq!(Rule::parse("b36s23").unwrap(), highlife);
+    assert_eq!(Rule::parse("S23/B36").unwrap(), highlife);
+    assert_eq!(Rule::parse("23/36").unwrap(), highlife);
+    assert_eq!(highlife.to_string(), "B36/S23");
+    assert_eq!(Rule::parse("B3/S23").unwrap(), Rule::default());
+
+    let seeds = Rule::parse("B2/S").unwrap();
+    assert!(seeds.birth[2] && seeds.survival.iter().all(|survives| !survives));
+
+    let brians_brain = Rule::parse("/2/3").unwrap();
+    assert_eq!(brians_brain.states, 3);
+    assert_eq!(Rule::parse("B2/S/C3").unwrap(), brians_brain);
+    assert_eq!(Rule::parse("b2sc3").unwrap(), brians_brain);
+    assert_eq!(brians_brain.to_string(), "B2/S/C3");
+    assert_eq!(Rule::parse("345/2/4").unwrap().to_string(), "B2/S345/C4");
+
+    let hex = Rule::parse("b2s34h").unwrap();
+    assert_eq!(hex.grid, Grid::Hexagonal);
+    assert_eq!(hex.to_string(), "B2/S34H");
+    assert_eq!(Rule::parse("34/2/3H").unwrap().to_string(), "B2/S34/C3H");
+    assert_eq!(Rule::parse("B45/S34L").unwrap().grid, Grid::Triangular);
+    assert!(Rule::parse("B7/S34H").is_err());
+    assert!(Rule::parse("B9/S34L").is_ok());
+
+    assert!(Rule::parse("B9/S23").is_err());
+    assert!(Rule::parse("B2/S/C1").is_err());
+    assert!(Rule::parse("/2/256").is_err());
+    assert!(Rule::parse("B3").is_err());
+    assert!(Rule::parse("garbage").is_err());
+}
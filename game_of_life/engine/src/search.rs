@@ -0,0 +1,164 @@
+//! Headless soup search.  Batches of seeded random soups are simulated off-screen with the
+//! bit-packed engine until they settle into a cycle, recording how long each one lived, its final
+//! population, and any rare objects in its final state.  Each soup is generated the same way as by
+//! `set_random_state`, so any of them can be replayed on a board of the same size from its seed.
+
+use std::fmt::Write;
+
+use crate::{
+    bitboard::BitBoard,
+    census::{take_census, CensusEntry, ObjectKind},
+    cycle::CycleDetector,
+    rule::Rule,
+    soup,
+    topology::Topology,
+};
+
+/// Named Conway's Life objects that turn up in almost every soup and aren't worth reporting
+const COMMON_LIFE_OBJECTS: &[&str] = &[
+    "block",
+    "blinker",
+    "beehive",
+    "glider",
+    "loaf",
+    "boat",
+    "ship",
+    "tub",
+    "pond",
+    "long boat",
+    "toad",
+    "beacon",
+];
+
+/// Settings shared by every soup in a search
+#[derive(Clone, Copy, Debug)]
+pub struct SearchParams {
+    pub width: usize,
+    pub height: usize,
+    pub density: f64,
+    pub rule: Rule,
+    pub topology: Topology,
+    /// Soups that haven't settled into a cycle after this many generations are given up on
+    pub max_generations: u64,
+}
+
+pub struct SoupResult {
+    pub seed: u32,
+    /// Generation at which the soup settled into a cycle, or `None` if it was still active after
+    /// `max_generations`
+    pub lifespan: Option<u64>,
+    pub period: Option<u64>,
+    /// Population at the end of the simulation
+    pub final_population: usize,
+    /// Rare objects in the final state.  Ones that were destroyed along the way aren't counted.
+    pub rare_objects: Vec<CensusEntry>,
+}
+
+/// Returns `true` if `entry` is an object worth reporting.  There's no notion of a common object
+/// under rules other than Conway's Life, so every stable object found under them is reported.
+fn is_rare(entry: &CensusEntry) -> bool {
+    match (entry.kind, entry.name) {
+        (ObjectKind::Unstable, _) => false,
+        (_, Some(name)) => !COMMON_LIFE_OBJECTS.contains(&name),
+        (_, None) => true,
+    }
+}
+
+/// Simulates the soup with the given seed until it settles into a cycle or runs out of
+/// generations.  The rule must be supported by the bit-packed engine.
+pub fn run_soup(seed: u32, params: &SearchParams) -> SoupResult {
+    let (width, height) = (params.width, params.height);
+    soup::seed_rng(seed);
+    let mut bits = BitBoard::new(width, height);
+    for i in 0..width * height {
+        if soup::random_cell(params.density).is_alive() {
+            bits.set(i % width, i / width, true);
+        }
+    }
+
+    let mut cycles = CycleDetector::default();
+    cycles.record_words(0, &bits.words, 0);
+    let mut generation = 0;
+    while cycles.cycle.is_none() && generation < params.max_generations {
+        bits = bits.step(&params.rule, params.topology);
+        generation += 1;
+        cycles.record_words(generation, &bits.words, 0);
+    }
+
+    let rare_objects = take_census(&bits.to_board(), &params.rule, params.topology)
+        .into_iter()
+        .filter(is_rare)
+        .collect();
+    SoupResult {
+        seed,
+        lifespan: cycles.cycle.map(|cycle| cycle.start() as u64),
+        period: cycles.cycle.map(|cycle| cycle.period() as u64),
+        final_population: bits.population(),
+        rare_objects,
+    }
+}
+
+fn json_option(value: Option<u64>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "null".to_owned(),
+    }
+}
+
+/// Encodes the results as a JSON object keyed by seed
+pub fn results_to_json(results: &[SoupResult]) -> String {
+    let mut out = String::from("{");
+    for (i, result) in results.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let rare_objects: Vec<String> = result
+            .rare_objects
+            .iter()
+            .map(CensusEntry::to_json)
+            .collect();
+        write!(
+            out,
+            "\"{}\":{{\"lifespan\":{},\"period\":{},\"final_population\":{},\"rare_objects\":[{}]}}",
+            result.seed,
+            json_option(result.lifespan),
+            json_option(result.period),
+            result.final_population,
+            rare_objects.join(",")
+        )
+        .unwrap();
+    }
+    out.push('}');
+    out
+}
+
+#[test]
+fn soups_are_replayable() {
+    let params = SearchParams {
+        width: 24,
+        height: 24,
+        density: 0.4,
+        rule: Rule::default(),
+        topology: Topology::Torus,
+        max_generations: 2000,
+    };
+
+    let results: Vec<SoupResult> = (0..4).map(|seed| run_soup(seed, &params)).collect();
+    let replayed: Vec<SoupResult> = (0..4).map(|seed| run_soup(seed, &params)).collect();
+    assert_eq!(results_to_json(&results), results_to_json(&replayed));
+    assert!(results.iter().any(|result| result.lifespan.is_some()));
+
+    // An empty soup is dead from the start
+    let empty = run_soup(
+        0,
+        &SearchParams {
+            density: 0.,
+            ..params
+        },
+    );
+    assert_eq!((empty.lifespan, empty.period), (Some(0), Some(1)));
+    assert_eq!(
+        results_to_json(&[empty]),
+        "{\"0\":{\"lifespan\":0,\"period\":1,\"final_population\":0,\"rare_objects\":[]}}"
+    );
+}
@@ -0,0 +1,115 @@
+//! Rectangular selections of the board that can be copied, cut, cleared, inverted, or filled, and
+//! the outline that is drawn over the canvas to show them.
+
+use crate::{patterns::Pattern, Board};
+
+/// Color of the outline drawn around the selection
+pub const SELECTION_OUTLINE_COLOR: [u8; 3] = [0, 160, 255];
+
+/// A rectangle of cells that lies entirely within the board
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Selection {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Selection {
+    /// Builds the selection spanning the two given corners, inclusive, clipped to the board.
+    /// Returns `None` if none of it lies on the board.
+    pub fn from_corners(
+        (x0, y0): (isize, isize),
+        (x1, y1): (isize, isize),
+        board_width: usize,
+        board_height: usize,
+    ) -> Option<Self> {
+        let min_x = x0.min(x1).max(0);
+        let min_y = y0.min(y1).max(0);
+        let max_x = x0.max(x1).min(board_width as isize - 1);
+        let max_y = y0.max(y1).min(board_height as isize - 1);
+        if min_x > max_x || min_y > max_y {
+            return None;
+        }
+
+        Some(Selection {
+            x: min_x as usize,
+            y: min_y as usize,
+            width: (max_x - min_x + 1) as usize,
+            height: (max_y - min_y + 1) as usize,
+        })
+    }
+
+    /// Returns the index of every selected cell on a board that is `board_width` cells wide
+    pub fn indices(self, board_width: usize) -> impl Iterator<Item = usize> {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| y * board_width + x))
+    }
+
+    /// Copies the selected cells, including dead ones, out of `board`
+    pub fn extract(self, board: &Board) -> Pattern {
+        let mut pattern = Pattern::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                pattern.set(x, y, board.cells[(self.y + y) * board.width + self.x + x]);
+            }
+        }
+        pattern
+    }
+
+    /// Returns the indices of the canvas pixels along the inside edge of the selection when each
+    /// cell is drawn as `scale` x `scale` pixels on a canvas `canvas_width` pixels wide
+    pub fn outline_pixels(self, scale: usize, canvas_width: usize) -> Vec<usize> {
+        let (min_x, min_y) = (self.x * scale, self.y * scale);
+        let max_x = (self.x + self.width) * scale - 1;
+        let max_y = (self.y + self.height) * scale - 1;
+
+        let mut pixels = Vec::with_capacity(2 * (max_x - min_x + max_y - min_y + 2));
+        for px in min_x..=max_x {
+            pixels.push(min_y * canvas_width + px);
+            if max_y != min_y {
+                pixels.push(max_y * canvas_width + px);
+            }
+        }
+        for py in min_y + 1..max_y {
+            pixels.push(py * canvas_width + min_x);
+            if max_x != min_x {
+                pixels.push(py * canvas_width + max_x);
+            }
+        }
+        pixels
+    }
+}
+
+#[test]
+fn selections_are_clipped_to_the_board() {
+    use crate::Cell;
+
+    let selection = Selection::from_corners((6, 4), (-2, 1), 5, 5).unwrap();
+    assert_eq!(
+        selection,
+        Selection {
+            x: 0,
+            y: 1,
+            width: 5,
+            height: 4
+        }
+    );
+    assert_eq!(Selection::from_corners((5, 0), (7, 3), 5, 5), None);
+
+    let mut board = Board {
+        width: 5,
+        height: 5,
+        cells: vec![Cell::Dead; 25].into_boxed_slice(),
+    };
+    board.cells[7] = Cell::Alive;
+    let selection = Selection::from_corners((1, 1), (2, 2), 5, 5).unwrap();
+    assert_eq!(selection.indices(5).collect::<Vec<_>>(), vec![6, 7, 11, 12]);
+    assert_eq!(selection.extract(&board).live_coords(), vec![(1, 0)]);
+
+    // A 2x2 cell selection drawn at 2x scale has a 4x4 pixel outline with 12 pixels
+    let mut outline = selection.outline_pixels(2, 10);
+    outline.sort();
+    assert_eq!(outline.len(), 12);
+    assert_eq!(&outline[..4], &[22, 23, 24, 25]);
+}
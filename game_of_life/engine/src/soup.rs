@@ -0,0 +1,65 @@
+//! Random soups generated from the seedable PCG in `sketches-util` so that any soup can be
+//! recreated exactly from its seed.
+
+use rand::Rng;
+use rand_pcg::Pcg32;
+use sketches_util::rng;
+
+use crate::Cell;
+
+/// Fraction of cells that start alive on a freshly created board
+pub const DEFAULT_DENSITY: f64 = 0.5;
+
+/// Spreads the bits of `x` across all 64 bits of the output (SplitMix64's finalizer)
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Resets the RNG to the stream identified by `seed`.  The seed is mixed into both the PCG's
+/// state and its stream so that neighboring seeds produce unrelated soups.
+pub fn seed_rng(seed: u32) {
+    // `reinit_rng` transmutes seeds into the PCG's raw state, allowing invalid even increments
+    *rng() = Pcg32::new(mix(seed as u64), mix(!(seed as u64)));
+}
+
+/// Returns an alive cell with probability `density` and a dead one otherwise
+#[inline]
+pub fn random_cell(density: f64) -> Cell {
+    if rng().gen::<f64>() < density {
+        Cell::Alive
+    } else {
+        Cell::Dead
+    }
+}
+
+/// Returns a cell in one of the `states - 1` states other than dead, picked uniformly, with
+/// probability `density` and a dead one otherwise
+pub fn random_state(density: f64, states: u8) -> Cell {
+    if rng().gen::<f64>() < density {
+        Cell::from_state_index(rng().gen_range(1, states))
+    } else {
+        Cell::Dead
+    }
+}
+
+#[test]
+fn soups_are_reproducible() {
+    let soup = |seed: u32, density: f64| -> Vec<Cell> {
+        seed_rng(seed);
+        (0..4096).map(|_| random_cell(density)).collect()
+    };
+
+    // Pins the generator so that seeds keep producing the same soups
+    seed_rng(7);
+    let outputs: Vec<u32> = (0..3).map(|_| rng().gen()).collect();
+    assert_eq!(outputs, [565266180, 241295640, 1010236511]);
+
+    assert_eq!(soup(7, 0.3), soup(7, 0.3));
+    assert_ne!(soup(7, 0.3), soup(8, 0.3));
+    let population = soup(0, 0.3).into_iter().filter(Cell::is_alive).count();
+    assert!(population > 1000 && population < 1450, "{}", population);
+    assert!(soup(1, 0.).into_iter().all(|cell| cell == Cell::Dead));
+    assert!(soup(1, 1.).into_iter().all(|cell| cell == Cell::Alive));
+}
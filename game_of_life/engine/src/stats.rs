@@ -0,0 +1,295 @@
+//! Population statistics recorded after each generation, along with a rolling time series of
+//! them that the JS can chart.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Board, Cell};
+
+/// Number of generations kept in the rolling time series
+const MAX_SERIES_LENGTH: usize = 4096;
+
+/// `(min_x, min_y, max_x, max_y)` of a group of cells, inclusive
+type Bounds = (usize, usize, usize, usize);
+
+/// Returns the smallest bounds that cover both `bounds` and `other`
+#[inline]
+fn union(bounds: Option<Bounds>, other: Bounds) -> Bounds {
+    match bounds {
+        Some((min_x, min_y, max_x, max_y)) => (
+            min_x.min(other.0),
+            min_y.min(other.1),
+            max_x.max(other.2),
+            max_y.max(other.3),
+        ),
+        None => other,
+    }
+}
+
+/// Returns the bounds of all live cells on `board`
+fn live_bounds(board: &Board) -> Option<Bounds> {
+    let mut bounds = None;
+    for (i, cell) in board.cells.iter().enumerate() {
+        if cell.is_alive() {
+            let (x, y) = (i % board.width, i / board.width);
+            bounds = Some(union(bounds, (x, y, x, y)));
+        }
+    }
+    bounds
+}
+
+/// Counts of the cells that changed state during a generation, along with where they were
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Transitions {
+    pub births: u32,
+    pub deaths: u32,
+    /// Width of the board that the cells are on
+    width: usize,
+    birth_bounds: Option<Bounds>,
+    death_bounds: Option<Bounds>,
+}
+
+impl Transitions {
+    pub fn new(board_width: usize) -> Self {
+        Transitions {
+            width: board_width,
+            ..Transitions::default()
+        }
+    }
+
+    /// Counts the cell at index `i`, which changed from `old` to `new`
+    #[inline]
+    pub fn record(&mut self, i: usize, old: Cell, new: Cell) {
+        let (x, y) = (i % self.width, i / self.width);
+        if new.is_alive() {
+            self.births += 1;
+            self.birth_bounds = Some(union(self.birth_bounds, (x, y, x, y)));
+        } else if old.is_alive() {
+            self.deaths += 1;
+            self.death_bounds = Some(union(self.death_bounds, (x, y, x, y)));
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GenerationStats {
+    generation: u64,
+    population: u32,
+    births: u32,
+    deaths: u32,
+    /// Bounds of all live cells
+    bounding_box: Option<Bounds>,
+}
+
+impl GenerationStats {
+    /// Counts the population and bounding box of `board` after it was advanced to `generation`
+    pub fn new(generation: u64, board: &Board, transitions: Transitions) -> Self {
+        GenerationStats {
+            generation,
+            population: board.cells.iter().filter(|cell| cell.is_alive()).count() as u32,
+            births: transitions.births,
+            deaths: transitions.deaths,
+            bounding_box: live_bounds(board),
+        }
+    }
+
+    /// Derives the statistics of the generation after this one from the cells that changed
+    /// during it.  `board` is only scanned if a cell on the edge of the bounding box died, since
+    /// that's the only way for the bounding box to shrink.
+    pub fn advance(&self, board: &Board, transitions: Transitions) -> Self {
+        let population = self.population + transitions.births - transitions.deaths;
+        let bounding_box = match (self.bounding_box, transitions.death_bounds) {
+            (Some(bounds), Some(deaths))
+                if deaths.0 == bounds.0
+                    || deaths.1 == bounds.1
+                    || deaths.2 == bounds.2
+                    || deaths.3 == bounds.3 =>
+            {
+                live_bounds(board)
+            }
+            (bounds, _) => match transitions.birth_bounds {
+                Some(births) => Some(union(bounds, births)),
+                None => bounds,
+            },
+        };
+
+        GenerationStats {
+            generation: self.generation + 1,
+            population,
+            births: transitions.births,
+            deaths: transitions.deaths,
+            bounding_box,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GenerationStats {
+    #[wasm_bindgen(getter)]
+    pub fn generation(&self) -> f64 {
+        self.generation as f64
+    }
+
+    /// Number of live cells, not counting dying cells of Generations rules
+    #[wasm_bindgen(getter)]
+    pub fn population(&self) -> u32 {
+        self.population
+    }
+
+    /// Number of cells that became alive during the generation
+    #[wasm_bindgen(getter)]
+    pub fn births(&self) -> u32 {
+        self.births
+    }
+
+    /// Number of live cells that died or started dying during the generation
+    #[wasm_bindgen(getter)]
+    pub fn deaths(&self) -> u32 {
+        self.deaths
+    }
+
+    /// Leftmost column containing a live cell, or -1 if there are none
+    #[wasm_bindgen(getter)]
+    pub fn min_x(&self) -> i32 {
+        self.bounding_box
+            .map(|(min_x, ..)| min_x as i32)
+            .unwrap_or(-1)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_y(&self) -> i32 {
+        self.bounding_box
+            .map(|(_, min_y, ..)| min_y as i32)
+            .unwrap_or(-1)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_x(&self) -> i32 {
+        self.bounding_box
+            .map(|(.., max_x, _)| max_x as i32)
+            .unwrap_or(-1)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_y(&self) -> i32 {
+        self.bounding_box
+            .map(|(.., max_y)| max_y as i32)
+            .unwrap_or(-1)
+    }
+}
+
+#[derive(Default)]
+pub struct StatsSeries {
+    series: VecDeque<GenerationStats>,
+    /// Set when the board was changed by something other than a generation, which means that the
+    /// latest entry no longer describes it
+    stale: bool,
+}
+
+impl StatsSeries {
+    /// Records the statistics of `board` after it was advanced to `generation`, deriving them
+    /// from the previous generation's if the board hasn't been changed since
+    pub fn record(&mut self, generation: u64, board: &Board, transitions: Transitions) {
+        let stats = match self.latest() {
+            Some(latest) if !self.stale && latest.generation + 1 == generation => {
+                latest.advance(board, transitions)
+            }
+            _ => GenerationStats::new(generation, board, transitions),
+        };
+        self.stale = false;
+        self.push(stats);
+    }
+
+    /// Marks the latest entry as out of date, which is needed whenever the board is changed by
+    /// something other than a generation
+    #[inline]
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn push(&mut self, stats: GenerationStats) {
+        self.series.push_back(stats);
+        if self.series.len() > MAX_SERIES_LENGTH {
+            self.series.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<GenerationStats> {
+        self.series.back().cloned()
+    }
+
+    /// Returns the generation of the oldest entry in the series
+    pub fn start_generation(&self) -> Option<u64> {
+        self.series.front().map(|stats| stats.generation)
+    }
+
+    /// Drops every entry after `generation`, which is needed when the board is rewound
+    pub fn truncate_after(&mut self, generation: u64) {
+        while self
+            .series
+            .back()
+            .map(|stats| stats.generation > generation)
+            .unwrap_or(false)
+        {
+            self.series.pop_back();
+        }
+    }
+
+    /// Flattens the series into `[population, births, deaths]` triples, oldest first
+    pub fn to_flat_vec(&self) -> Vec<u32> {
+        let mut flat = Vec::with_capacity(self.series.len() * 3);
+        for stats in &self.series {
+            flat.extend_from_slice(&[stats.population, stats.births, stats.deaths]);
+        }
+        flat
+    }
+}
+
+#[test]
+fn stats_track_population_and_bounds() {
+    let mut board = Board {
+        width: 6,
+        height: 4,
+        cells: vec![Cell::Dead; 24].into_boxed_slice(),
+    };
+    board.cells[8] = Cell::Alive;
+    board.cells[15] = Cell::Alive;
+    board.cells[21] = Cell::Dying(2);
+    let stats = GenerationStats::new(3, &board, Transitions::default());
+    assert_eq!(stats.population(), 2);
+    assert_eq!(
+        (stats.min_x(), stats.min_y(), stats.max_x(), stats.max_y()),
+        (2, 1, 3, 2)
+    );
+
+    // Births grow the bounding box, deaths inside of it leave it alone, and deaths on its edge
+    // shrink it
+    let mut series = StatsSeries::default();
+    series.push(stats);
+    let steps = [
+        vec![(21, Cell::Alive), (12, Cell::Alive), (14, Cell::Alive)],
+        vec![(14, Cell::Dead)],
+        vec![(21, Cell::Dying(2))],
+    ];
+    for (generation, changes) in (4..).zip(steps.iter()) {
+        let mut transitions = Transitions::new(board.width);
+        for &(i, cell) in changes {
+            transitions.record(i, board.cells[i], cell);
+            board.cells[i] = cell;
+        }
+        series.record(generation, &board, transitions);
+        assert_eq!(
+            series.latest(),
+            Some(GenerationStats::new(generation, &board, transitions))
+        );
+    }
+    assert_eq!(
+        series.to_flat_vec(),
+        vec![2, 0, 0, 5, 3, 0, 4, 0, 1, 3, 0, 1]
+    );
+
+    series.truncate_after(3);
+    assert_eq!(series.latest(), Some(stats));
+}
@@ -0,0 +1,93 @@
+//! Boundary conditions that determine what lies past the edges of the board
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Topology {
+    /// Everything outside the board is dead
+    Bounded,
+    /// Opposite edges are joined together, so cells leaving one side re-enter on the other
+    Torus,
+    /// Left/right edges are joined like a torus, but the top/bottom edges are joined with a
+    /// horizontal flip
+    KleinBottle,
+    /// Edges act like mirrors; cells past the edge are reflections of the cells inside it
+    Mirror,
+    /// Everything outside the board is permanently alive
+    AliveEdges,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Topology::Bounded
+    }
+}
+
+/// Wraps `coord` into `0..size`
+#[inline]
+fn wrap(coord: isize, size: isize) -> isize {
+    coord.rem_euclid(size)
+}
+
+/// Reflects `coord` back into `0..size` as if the edges were mirrors placed between the edge
+/// cells and the cells just past them.
+#[inline]
+fn reflect(coord: isize, size: isize) -> isize {
+    let coord = coord.rem_euclid(size * 2);
+    if coord >= size {
+        (size * 2) - 1 - coord
+    } else {
+        coord
+    }
+}
+
+impl Topology {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "bounded" | "dead" => Ok(Topology::Bounded),
+            "torus" | "toroidal" | "wrap" => Ok(Topology::Torus),
+            "klein" | "klein_bottle" | "kleinbottle" => Ok(Topology::KleinBottle),
+            "mirror" | "mirrored" | "reflect" => Ok(Topology::Mirror),
+            "alive" | "alive_edges" => Ok(Topology::AliveEdges),
+            _ => Err(format!("Unknown topology: \"{}\"", name)),
+        }
+    }
+
+    /// Maps a coordinate that may lie outside of a `width` x `height` board to the on-board cell
+    /// that it corresponds to.  Returns `None` if the topology has no on-board equivalent for it,
+    /// which is the case for all off-board coordinates with `Bounded` and `AliveEdges`.
+    pub fn map_coord(
+        self,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let (width, height) = (width as isize, height as isize);
+        if x >= 0 && y >= 0 && x < width && y < height {
+            return Some((x as usize, y as usize));
+        }
+
+        let (x, y) = match self {
+            Topology::Bounded | Topology::AliveEdges => return None,
+            Topology::Torus => (wrap(x, width), wrap(y, height)),
+            Topology::KleinBottle => {
+                // Every trip across the top/bottom seam flips the board horizontally
+                let flips = y.div_euclid(height);
+                let x = if flips % 2 != 0 { width - 1 - x } else { x };
+                (wrap(x, width), wrap(y, height))
+            }
+            Topology::Mirror => (reflect(x, width), reflect(y, height)),
+        };
+        Some((x as usize, y as usize))
+    }
+}
+
+#[test]
+fn topology_coord_mapping() {
+    assert_eq!(Topology::Bounded.map_coord(-1, 0, 10, 5), None);
+    assert_eq!(Topology::Bounded.map_coord(3, 4, 10, 5), Some((3, 4)));
+    assert_eq!(Topology::Torus.map_coord(-1, 5, 10, 5), Some((9, 0)));
+    assert_eq!(Topology::KleinBottle.map_coord(2, -1, 10, 5), Some((7, 4)));
+    assert_eq!(Topology::KleinBottle.map_coord(-1, 2, 10, 5), Some((9, 2)));
+    assert_eq!(Topology::Mirror.map_coord(-1, 5, 10, 5), Some((0, 4)));
+    assert_eq!(Topology::Mirror.map_coord(10, -2, 10, 5), Some((9, 1)));
+}
@@ -0,0 +1,79 @@
+//! Unbounded simulation backends that live alongside the fixed-size board.  While one is active,
+//! the board shows the part of it visible through the viewport.
+
+use crate::{hashlife::HashLife, rule::Rule, sparse::SparsePlane};
+
+pub enum Universe {
+    HashLife(HashLife),
+    Sparse(SparsePlane),
+}
+
+impl Universe {
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        match self {
+            Universe::HashLife(universe) => universe.set(x, y, alive),
+            Universe::Sparse(plane) => plane.set(x, y, alive),
+        }
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        match self {
+            Universe::HashLife(universe) => universe.set_rule(rule),
+            Universe::Sparse(plane) => plane.rule = rule,
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        match self {
+            Universe::HashLife(universe) => universe.generation,
+            Universe::Sparse(plane) => plane.generation,
+        }
+    }
+
+    /// Advances the universe by a single generation
+    pub fn step(&mut self) {
+        match self {
+            Universe::HashLife(universe) => universe.step(0),
+            Universe::Sparse(plane) => plane.step(),
+        }
+    }
+
+    /// Converts to a HashLife universe containing the same cells, if it isn't one already
+    pub fn into_hashlife(self, rule: Rule) -> HashLife {
+        match self {
+            Universe::HashLife(universe) => universe,
+            Universe::Sparse(plane) => {
+                let mut universe = HashLife::from_cells(rule, plane.live_cells);
+                universe.generation = plane.generation;
+                universe
+            }
+        }
+    }
+
+    /// Converts to a sparse plane containing the same cells, if it isn't one already
+    pub fn into_sparse(self, rule: Rule) -> SparsePlane {
+        match self {
+            Universe::HashLife(universe) => {
+                let mut plane = SparsePlane::from_cells(rule, universe.live_cells());
+                plane.generation = universe.generation;
+                plane
+            }
+            Universe::Sparse(plane) => plane,
+        }
+    }
+
+    pub fn for_each_live_block<F: FnMut(usize, usize)>(
+        &mut self,
+        x: i64,
+        y: i64,
+        zoom: u32,
+        cols: usize,
+        rows: usize,
+        f: F,
+    ) {
+        match self {
+            Universe::HashLife(universe) => universe.for_each_live_block(x, y, zoom, cols, rows, f),
+            Universe::Sparse(plane) => plane.for_each_live_block(x, y, zoom, cols, rows, f),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Window onto an unbounded universe that gets mapped onto the board and canvas
+
+/// Zoom levels beyond this are clamped to keep universe coordinates from overflowing
+pub const MAX_ZOOM: u32 = 32;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Viewport {
+    /// Universe coordinate of the cell shown in the top-left corner of the board
+    pub x: i64,
+    pub y: i64,
+    /// Each board cell shows a `2^zoom` x `2^zoom` block of universe cells, drawn alive if any
+    /// cell in the block is alive.
+    pub zoom: u32,
+}
+
+impl Viewport {
+    /// Creates a viewport with its top-left corner snapped down to a multiple of the block size
+    /// so that blocks line up with the universe's grid.
+    pub fn new(x: i64, y: i64, zoom: u32) -> Self {
+        let zoom = zoom.min(MAX_ZOOM);
+        let mask = !((1i64 << zoom) - 1);
+        Viewport {
+            x: x & mask,
+            y: y & mask,
+            zoom,
+        }
+    }
+
+    /// Maps a board coordinate to the universe coordinate of the top-left cell of its block
+    #[inline]
+    pub fn to_universe(&self, board_x: usize, board_y: usize) -> (i64, i64) {
+        (
+            self.x + ((board_x as i64) << self.zoom),
+            self.y + ((board_y as i64) << self.zoom),
+        )
+    }
+}
@@ -2,8 +2,14 @@ import * as React from 'react';
 import * as R from 'ramda';
 import { style } from 'typestyle';
 
-import { pause, resume } from '..';
-import { getIndex, CELL_COUNT } from '../util';
+import {
+  pause,
+  resume,
+  lastClickedCell,
+  setGenerationsPerFrame,
+  setPaintState as setEnginePaintState,
+} from '..';
+import { getIndex, CELL_COUNT, WORLD_WIDTH, WORLD_HEIGHT } from '../util';
 
 const blankWith = (toSet: number[][]) => {
   const state = new Uint8Array(CELL_COUNT);
@@ -64,6 +70,756 @@ const styles = {
 
 const ButtonRow = ({ children }) => <div className={styles.buttonRow}>{children}</div>;
 
+const RuleInput = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [rule, setRule] = React.useState('B3/S23');
+  const [isValid, setIsValid] = React.useState(true);
+
+  return (
+    <ButtonRow>
+      <input
+        value={rule}
+        onChange={e => setRule(e.target.value)}
+        style={{ borderColor: isValid ? undefined : 'red' }}
+      />
+      <button onClick={() => setIsValid(engine.set_rule(rule))}>Set Rule</button>
+    </ButtonRow>
+  );
+};
+
+const TopologySelect = ({ engine }: { engine: typeof import('../engine') }) => (
+  <ButtonRow>
+    <label>
+      Edges{' '}
+      <select onChange={e => engine.set_topology(e.target.value)} defaultValue="bounded">
+        <option value="bounded">Dead</option>
+        <option value="torus">Torus</option>
+        <option value="klein">Klein Bottle</option>
+        <option value="mirror">Mirrored</option>
+        <option value="alive">Alive</option>
+      </select>
+    </label>
+  </ButtonRow>
+);
+
+/** Reversible Margolus block rules, which `step_back` undoes exactly */
+const BLOCK_RULES = ['critters', 'tron', 'bbm'];
+
+const AutomatonSelect = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [paintState, setPaintState] = React.useState(1);
+
+  const selectAutomaton = (name: string) => {
+    if (name === 'life') {
+      engine.set_rule('B3/S23');
+    } else if (BLOCK_RULES.includes(name)) {
+      engine.set_rule(name);
+    } else {
+      engine.set_automaton(name);
+    }
+    setPaintState(1);
+    setEnginePaintState(1);
+  };
+
+  return (
+    <ButtonRow>
+      <label>
+        Automaton{' '}
+        <select onChange={e => selectAutomaton(e.target.value)} defaultValue="life">
+          <option value="life">Conway's Life</option>
+          <option value="wireworld">Wireworld</option>
+          <option value="briansbrain">Brian's Brain</option>
+          <option value="critters">Critters (Margolus)</option>
+          <option value="tron">Tron (Margolus)</option>
+          <option value="bbm">Billiard Ball Machine (Margolus)</option>
+        </select>
+      </label>
+      <label>
+        Paint state{' '}
+        <input
+          type="number"
+          min={1}
+          value={paintState}
+          onChange={e => {
+            setPaintState(+e.target.value);
+            setEnginePaintState(+e.target.value);
+          }}
+          style={{ width: 50 }}
+        />
+      </label>
+      <span>(Wireworld: 1 = electron head, 2 = tail, 3 = conductor)</span>
+    </ButtonRow>
+  );
+};
+
+const OneDControls = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [rule, setRule] = React.useState('W30');
+  const [isValid, setIsValid] = React.useState(true);
+  const [seed, setSeed] = React.useState(1);
+  const [density, setDensity] = React.useState(0.5);
+  const [customRow, setCustomRow] = React.useState('1011');
+
+  const seedCustom = () =>
+    engine.seed_1d_custom(new Uint8Array(customRow.split('').map(digit => +digit)));
+
+  return (
+    <div>
+      <ButtonRow>
+        <label>
+          1D rule{' '}
+          <input
+            value={rule}
+            onChange={e => setRule(e.target.value)}
+            style={{ borderColor: isValid ? undefined : 'red', width: 120 }}
+          />
+        </label>
+        <button
+          onClick={() => {
+            const valid = engine.set_rule(rule);
+            setIsValid(valid);
+            if (valid) {
+              engine.seed_1d_single();
+            }
+          }}
+        >
+          Set 1D Rule
+        </button>
+        <span>(W0-W255, or T&lt;code&gt;,R&lt;radius&gt;,K&lt;states&gt;)</span>
+      </ButtonRow>
+      <ButtonRow>
+        <button onClick={engine.seed_1d_single}>Single Cell</button>
+        <label>
+          Seed{' '}
+          <input
+            type="number"
+            min={0}
+            value={seed}
+            onChange={e => setSeed(+e.target.value >>> 0)}
+            style={{ width: 100 }}
+          />
+        </label>
+        <label>
+          Density{' '}
+          <input
+            type="number"
+            min={0}
+            max={1}
+            step={0.05}
+            value={density}
+            onChange={e => setDensity(+e.target.value)}
+            style={{ width: 60 }}
+          />
+        </label>
+        <button onClick={() => engine.seed_1d_random(seed, density)}>Random Row</button>
+        <input
+          value={customRow}
+          onChange={e => setCustomRow(e.target.value.replace(/[^0-9]/g, ''))}
+          style={{ width: 120 }}
+        />
+        <button onClick={seedCustom}>Custom Row</button>
+      </ButtonRow>
+    </div>
+  );
+};
+
+const RenderModeSelect = ({ engine }: { engine: typeof import('../engine') }) => (
+  <ButtonRow>
+    <label>
+      Color by{' '}
+      <select onChange={e => engine.set_render_mode(e.target.value)} defaultValue="states">
+        <option value="states">State</option>
+        <option value="age">Age</option>
+        <option value="fade">Fade</option>
+        <option value="heatmap">Heatmap</option>
+      </select>
+    </label>
+  </ButtonRow>
+);
+
+const StateColorPicker = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [cellState, setCellState] = React.useState(1);
+  const [color, setColor] = React.useState('#ffffff');
+
+  const apply = () => {
+    const [r, g, b] = [1, 3, 5].map(i => parseInt(color.slice(i, i + 2), 16));
+    engine.set_state_color(cellState, r, g, b);
+  };
+
+  return (
+    <ButtonRow>
+      <label>
+        State{' '}
+        <input
+          type="number"
+          min={0}
+          max={255}
+          value={cellState}
+          onChange={e => setCellState(+e.target.value)}
+          style={{ width: 40 }}
+        />
+      </label>
+      <input type="color" value={color} onChange={e => setColor(e.target.value)} />
+      <button onClick={apply}>Set Color</button>
+    </ButtonRow>
+  );
+};
+
+const PatternIO = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [text, setText] = React.useState('');
+  const [format, setFormat] = React.useState('rle');
+  const [error, setError] = React.useState<string | null>(null);
+  const [rotation, setRotation] = React.useState(0);
+  const [flip, setFlip] = React.useState(false);
+  const [blendMode, setBlendMode] = React.useState('or');
+
+  const load = () => {
+    const loader = {
+      rle: engine.load_rle,
+      plaintext: engine.load_plaintext,
+      life106: engine.load_life106,
+    }[format];
+    try {
+      loader(text, 10, 10);
+      setError(null);
+    } catch (err) {
+      setError(`Line ${err.line}: ${err.message}`);
+    }
+  };
+  const exportPattern = () => {
+    const exporter = {
+      rle: engine.export_rle,
+      plaintext: engine.export_plaintext,
+      life106: engine.export_life106,
+    }[format];
+    setText(exporter());
+  };
+  const stamp = () => {
+    try {
+      engine.stamp_pattern(text, 10, 10, rotation, flip, blendMode);
+      setError(null);
+    } catch (err) {
+      setError(err.line ? `Line ${err.line}: ${err.message}` : err.message);
+    }
+  };
+
+  return (
+    <div>
+      <textarea
+        value={text}
+        onChange={e => setText(e.target.value)}
+        rows={8}
+        cols={60}
+        placeholder="Paste a pattern here"
+      />
+      {error ? <div style={{ color: 'red' }}>{error}</div> : null}
+      <ButtonRow>
+        <select value={format} onChange={e => setFormat(e.target.value)}>
+          <option value="rle">RLE</option>
+          <option value="plaintext">Plaintext (.cells)</option>
+          <option value="life106">Life 1.06</option>
+        </select>
+        <button onClick={load}>Load Pattern</button>
+        <button onClick={exportPattern}>Export Pattern</button>
+      </ButtonRow>
+      <ButtonRow>
+        <select value={rotation} onChange={e => setRotation(+e.target.value)}>
+          <option value={0}>0°</option>
+          <option value={1}>90°</option>
+          <option value={2}>180°</option>
+          <option value={3}>270°</option>
+        </select>
+        <label>
+          <input type="checkbox" checked={flip} onChange={e => setFlip(e.target.checked)} /> Mirror
+        </label>
+        <select value={blendMode} onChange={e => setBlendMode(e.target.value)}>
+          <option value="overwrite">Overwrite</option>
+          <option value="or">OR</option>
+          <option value="xor">XOR</option>
+        </select>
+        <button onClick={stamp}>Stamp Pattern</button>
+      </ButtonRow>
+    </div>
+  );
+};
+
+const SelectionControls = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [density, setDensity] = React.useState(0.5);
+
+  return (
+    <ButtonRow>
+      <span>Selection (shift + drag):</span>
+      <button onClick={() => engine.copy_selection()}>Copy</button>
+      <button onClick={() => engine.cut_selection()}>Cut</button>
+      <button onClick={() => engine.paste(lastClickedCell.x, lastClickedCell.y)}>Paste</button>
+      <button onClick={() => engine.clear_selection()}>Clear</button>
+      <button onClick={() => engine.invert_selection()}>Invert</button>
+      <button onClick={() => engine.fill_selection_random(density)}>Random Fill</button>
+      <input
+        type="number"
+        min={0}
+        max={1}
+        step={0.05}
+        value={density}
+        onChange={e => setDensity(+e.target.value)}
+        style={{ width: 60 }}
+      />
+      <button onClick={engine.deselect}>Deselect</button>
+    </ButtonRow>
+  );
+};
+
+const SoupControls = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [seed, setSeed] = React.useState(1);
+  const [density, setDensity] = React.useState(0.5);
+
+  const generate = (seed: number) => {
+    setSeed(seed);
+    engine.set_random_state(seed, density, new Uint32Array(0));
+  };
+
+  return (
+    <ButtonRow>
+      <label>
+        Seed{' '}
+        <input
+          type="number"
+          min={0}
+          value={seed}
+          onChange={e => setSeed(+e.target.value >>> 0)}
+          style={{ width: 100 }}
+        />
+      </label>
+      <label>
+        Density{' '}
+        <input
+          type="number"
+          min={0}
+          max={1}
+          step={0.05}
+          value={density}
+          onChange={e => setDensity(+e.target.value)}
+          style={{ width: 60 }}
+        />
+      </label>
+      <button onClick={() => generate(seed)}>Generate Soup</button>
+      <button onClick={() => generate(Math.floor(Math.random() * 0xffffffff))}>Random Seed</button>
+    </ButtonRow>
+  );
+};
+
+const SpeedControl = () => {
+  const [generations, setGenerations] = React.useState(1);
+
+  return (
+    <ButtonRow>
+      <label>
+        Generations per frame{' '}
+        <input
+          type="range"
+          min={1}
+          max={100}
+          value={generations}
+          onChange={e => {
+            setGenerations(+e.target.value);
+            setGenerationsPerFrame(+e.target.value);
+          }}
+        />{' '}
+        {generations}
+      </label>
+    </ButtonRow>
+  );
+};
+
+const SeekControls = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [generation, setGeneration] = React.useState(0);
+
+  return (
+    <ButtonRow>
+      <label>
+        Generation{' '}
+        <input
+          type="number"
+          min={0}
+          value={generation}
+          onChange={e => setGeneration(+e.target.value)}
+          style={{ width: 60 }}
+        />
+      </label>
+      <button onClick={() => engine.seek(generation)}>Seek</button>
+      <button onClick={() => setGeneration(engine.get_generation())}>Current</button>
+    </ButtonRow>
+  );
+};
+
+const CycleStatus = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [status, setStatus] = React.useState('');
+
+  const check = () => {
+    const cycle = engine.get_cycle_info();
+    if (!cycle) {
+      setStatus('No cycle detected');
+    } else if (cycle.period === 1) {
+      setStatus(`Static since generation ${cycle.start}`);
+    } else {
+      setStatus(`Period ${cycle.period} since generation ${cycle.start}`);
+    }
+  };
+
+  return (
+    <ButtonRow>
+      <button onClick={check}>Check for Cycle</button>
+      <span>{status}</span>
+    </ButtonRow>
+  );
+};
+
+const STATS_CHART_WIDTH = 300;
+const STATS_CHART_HEIGHT = 60;
+
+const StatsPanel = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [summary, setSummary] = React.useState('');
+  const [points, setPoints] = React.useState('');
+
+  const refresh = () => {
+    const stats = engine.get_stats();
+    if (!stats) {
+      setSummary('No generations recorded yet');
+      setPoints('');
+      return;
+    }
+    const bounds =
+      stats.min_x < 0
+        ? 'empty'
+        : `(${stats.min_x}, ${stats.min_y}) to (${stats.max_x}, ${stats.max_y})`;
+    setSummary(
+      `Generation ${stats.generation}: population ${stats.population}, ${stats.births} births, ` +
+        `${stats.deaths} deaths, bounds ${bounds}`
+    );
+
+    // The series is made up of [population, births, deaths] triples
+    const series = engine.get_stats_series();
+    const populations = series.filter((_, i) => i % 3 === 0);
+    const maxPopulation = Math.max(1, ...populations);
+    const xStep = STATS_CHART_WIDTH / Math.max(1, populations.length - 1);
+    setPoints(
+      populations
+        .map(
+          (population, i) =>
+            `${i * xStep},${STATS_CHART_HEIGHT - (population / maxPopulation) * STATS_CHART_HEIGHT}`
+        )
+        .join(' ')
+    );
+  };
+
+  return (
+    <div>
+      <ButtonRow>
+        <button onClick={refresh}>Refresh Stats</button>
+        <span>{summary}</span>
+      </ButtonRow>
+      <svg width={STATS_CHART_WIDTH} height={STATS_CHART_HEIGHT}>
+        <polyline points={points} fill="none" stroke="black" />
+      </svg>
+    </div>
+  );
+};
+
+interface CensusEntry {
+  code: string;
+  name: string | null;
+  kind: string;
+  period: number;
+  dx: number;
+  dy: number;
+  population: number;
+  count: number;
+}
+
+const gcd = (a: number, b: number): number => (b === 0 ? a : gcd(b, a % b));
+
+/** Formats the speed of a spaceship in the usual notation, like "c/4 diagonal" for the glider */
+const spaceshipSpeed = ({ dx, dy, period }: CensusEntry): string => {
+  if (dy === 0 || dy === dx) {
+    const divisor = gcd(dx, period);
+    const [distance, generations] = [dx / divisor, period / divisor];
+    const direction = dy === 0 ? 'orthogonal' : 'diagonal';
+    return `${distance === 1 ? '' : distance}c/${generations} ${direction}`;
+  }
+
+  const divisor = gcd(gcd(dx, dy), period);
+  return `(${dx / divisor},${dy / divisor})c/${period / divisor}`;
+};
+
+const CensusPanel = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [census, setCensus] = React.useState<CensusEntry[] | null>(null);
+  const [json, setJson] = React.useState('');
+
+  const takeCensus = () => {
+    const json = engine.get_census();
+    setJson(json || '');
+    setCensus(json ? JSON.parse(json) : null);
+  };
+
+  return (
+    <div>
+      <ButtonRow>
+        <button onClick={takeCensus}>Take Census</button>
+        {json ? (
+          <a href={`data:application/json,${encodeURIComponent(json)}`} download="census.json">
+            Export JSON
+          </a>
+        ) : null}
+      </ButtonRow>
+      {census ? (
+        <table>
+          <thead>
+            <tr>
+              <th>Count</th>
+              <th>Object</th>
+              <th>Kind</th>
+              <th>Code</th>
+            </tr>
+          </thead>
+          <tbody>
+            {census.map(entry => (
+              <tr key={entry.code}>
+                <td>{entry.count}</td>
+                <td>{entry.name || ''}</td>
+                <td>
+                  {entry.kind === 'spaceship'
+                    ? `${spaceshipSpeed(entry)} spaceship`
+                    : entry.kind === 'oscillator'
+                    ? `p${entry.period} oscillator`
+                    : entry.kind.replace('_', ' ')}
+                </td>
+                <td>{entry.code}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      ) : null}
+    </div>
+  );
+};
+
+interface SoupResult {
+  lifespan: number | null;
+  period: number | null;
+  final_population: number;
+  rare_objects: CensusEntry[];
+}
+
+/** Number of soups simulated between yields to the browser while searching */
+const SOUP_SEARCH_CHUNK_SIZE = 20;
+
+const SoupSearch = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [firstSeed, setFirstSeed] = React.useState(1);
+  const [count, setCount] = React.useState(1000);
+  const [density, setDensity] = React.useState(0.5);
+  const [maxGenerations, setMaxGenerations] = React.useState(10000);
+  const [results, setResults] = React.useState<{ [seed: string]: SoupResult }>({});
+  const [searching, setSearching] = React.useState(false);
+  const cancelled = React.useRef(false);
+  // Replays need the density that the soups were generated with, even if it's been changed since
+  const searchDensity = React.useRef(density);
+
+  // Soups are the same size as the board so that they can be replayed on it
+  const search = () => {
+    cancelled.current = false;
+    searchDensity.current = density;
+    setSearching(true);
+    let accumulated: { [seed: string]: SoupResult } = {};
+    setResults(accumulated);
+
+    const runChunk = (offset: number) => {
+      const chunkSize = Math.min(SOUP_SEARCH_CHUNK_SIZE, count - offset);
+      if (cancelled.current || chunkSize <= 0) {
+        setSearching(false);
+        return;
+      }
+
+      const json = engine.search_soups(
+        firstSeed + offset,
+        chunkSize,
+        WORLD_WIDTH,
+        WORLD_HEIGHT,
+        density,
+        maxGenerations
+      );
+      if (!json) {
+        setSearching(false);
+        return;
+      }
+      accumulated = { ...accumulated, ...JSON.parse(json) };
+      setResults(accumulated);
+      setTimeout(() => runChunk(offset + chunkSize), 0);
+    };
+    runChunk(0);
+  };
+
+  const seeds = Object.keys(results);
+  // Soups that never settled are the most interesting, followed by the longest-lived ones
+  const notable = seeds
+    .slice()
+    .sort((a, b) => {
+      const lifespan = (seed: string) =>
+        results[seed].lifespan === null ? Infinity : results[seed].lifespan!;
+      return lifespan(b) - lifespan(a);
+    })
+    .slice(0, 10);
+
+  return (
+    <div>
+      <ButtonRow>
+        <label>
+          First seed{' '}
+          <input
+            type="number"
+            value={firstSeed}
+            onChange={e => setFirstSeed(+e.target.value >>> 0)}
+            style={{ width: 90 }}
+          />
+        </label>
+        <label>
+          Soups{' '}
+          <input
+            type="number"
+            min={1}
+            value={count}
+            onChange={e => setCount(+e.target.value)}
+            style={{ width: 70 }}
+          />
+        </label>
+        <label>
+          Density{' '}
+          <input
+            type="number"
+            min={0}
+            max={1}
+            step={0.05}
+            value={density}
+            onChange={e => setDensity(+e.target.value)}
+            style={{ width: 60 }}
+          />
+        </label>
+        <label>
+          Max generations{' '}
+          <input
+            type="number"
+            min={1}
+            value={maxGenerations}
+            onChange={e => setMaxGenerations(+e.target.value)}
+            style={{ width: 80 }}
+          />
+        </label>
+        {searching ? (
+          <button onClick={() => (cancelled.current = true)}>Stop Search</button>
+        ) : (
+          <button onClick={search}>Search Soups</button>
+        )}
+        <span>
+          {seeds.length} / {count} soups
+        </span>
+        {seeds.length > 0 && !searching ? (
+          <a
+            href={`data:application/json,${encodeURIComponent(JSON.stringify(results))}`}
+            download="soups.json"
+          >
+            Export JSON
+          </a>
+        ) : null}
+      </ButtonRow>
+      {notable.map(seed => {
+        const { lifespan, period, final_population, rare_objects } = results[seed];
+        return (
+          <ButtonRow key={seed}>
+            <span>
+              Seed {seed}:{' '}
+              {lifespan === null ? 'still active' : `settled at ${lifespan} (period ${period})`},
+              population {final_population}
+              {rare_objects.length > 0
+                ? `, rare objects in final state: ${rare_objects
+                    .map(object => object.name || object.code)
+                    .join(', ')}`
+                : ''}
+            </span>
+            <button
+              onClick={() =>
+                engine.set_random_state(+seed, searchDensity.current, new Uint32Array(0))
+              }
+            >
+              Replay
+            </button>
+          </ButtonRow>
+        );
+      })}
+    </div>
+  );
+};
+
+const PAN_STEP = 16;
+
+const UnboundedControls = ({ engine }: { engine: typeof import('../engine') }) => {
+  const [exponent, setExponent] = React.useState(6);
+  const [generation, setGeneration] = React.useState(-1);
+  const [viewport, setViewportState] = React.useState({ x: 0, y: 0, zoom: 0 });
+
+  const moveViewport = (dx: number, dy: number, dZoom: number) => {
+    const zoom = Math.max(0, viewport.zoom + dZoom);
+    const blockSize = Math.pow(2, zoom);
+    const newViewport = {
+      x: viewport.x + dx * PAN_STEP * blockSize,
+      y: viewport.y + dy * PAN_STEP * blockSize,
+      zoom,
+    };
+    engine.set_viewport(newViewport.x, newViewport.y, newViewport.zoom);
+    setViewportState(newViewport);
+  };
+
+  return (
+    <div>
+      <ButtonRow>
+        <label>
+          Jump 2^
+          <input
+            type="number"
+            min={0}
+            max={48}
+            value={exponent}
+            onChange={e => setExponent(+e.target.value)}
+            style={{ width: 40 }}
+          />{' '}
+          generations
+        </label>
+        <button
+          onClick={() => {
+            engine.step_generations(exponent);
+            setGeneration(engine.get_universe_generation());
+          }}
+        >
+          Jump
+        </button>
+        <button onClick={engine.enable_sparse_plane}>Unbounded Plane</button>
+        <button
+          onClick={() => {
+            engine.exit_unbounded_plane();
+            setGeneration(-1);
+          }}
+        >
+          Exit Unbounded
+        </button>
+        {generation >= 0 ? <span>Generation {generation}</span> : null}
+      </ButtonRow>
+      <ButtonRow>
+        <button onClick={() => moveViewport(-1, 0, 0)}>←</button>
+        <button onClick={() => moveViewport(1, 0, 0)}>→</button>
+        <button onClick={() => moveViewport(0, -1, 0)}>↑</button>
+        <button onClick={() => moveViewport(0, 1, 0)}>↓</button>
+        <button onClick={() => moveViewport(0, 0, -1)}>Zoom In</button>
+        <button onClick={() => moveViewport(0, 0, 1)}>Zoom Out</button>
+      </ButtonRow>
+    </div>
+  );
+};
+
 const Writeup = () => (
   <div>
     <h2>Genetic Law Verification</h2>
@@ -105,15 +861,38 @@ export default ({ engine }: { engine: typeof import('../engine') }) => (
       <button onClick={pause}>Pause</button>
       <button onClick={resume}>Resume</button>
       <button onClick={engine.tick}>Step</button>
+      <button onClick={engine.step_back}>Step Back</button>
       <button onClick={R.partial(engine.set_state, [new Uint8Array(CELL_COUNT)])}>Clear</button>
     </ButtonRow>
+    <SpeedControl />
+    <RuleInput engine={engine} />
+    <AutomatonSelect engine={engine} />
+    <OneDControls engine={engine} />
+    <TopologySelect engine={engine} />
+    <RenderModeSelect engine={engine} />
+    <SelectionControls engine={engine} />
+    <SoupControls engine={engine} />
+    <StateColorPicker engine={engine} />
+    <SeekControls engine={engine} />
+    <CycleStatus engine={engine} />
+    <StatsPanel engine={engine} />
+    <CensusPanel engine={engine} />
+    <SoupSearch engine={engine} />
+    <PatternIO engine={engine} />
+    <UnboundedControls engine={engine} />
     <ButtonRow>
       <button onClick={() => engine.set_state(geneticLawsInitalState())}>
         Verify Genetic Laws
       </button>
     </ButtonRow>
     <ButtonRow>
-      <button onClick={engine.set_random_state}>Demonstrate Emergent Properties I</button>
+      <button
+        onClick={() =>
+          engine.set_random_state(Math.floor(Math.random() * 0xffffffff), 0.5, new Uint32Array(0))
+        }
+      >
+        Demonstrate Emergent Properties I
+      </button>
       <button onClick={() => engine.set_state(getGrowWithoutLimitInitalState())}>
         Demonstrate Emergent Properties II
       </button>
@@ -4,15 +4,34 @@ import * as ReactDOM from 'react-dom';
 const wasm = import('./engine');
 const asyncWasmShim = import('./wasmShim');
 import App from './components/App';
-import { CANVAS_SCALE_FACTOR } from './util';
+import { CANVAS_SCALE_FACTOR, WORLD_WIDTH, WORLD_HEIGHT } from './util';
 
 const canvas = document.getElementById('canvas') as HTMLCanvasElement;
+canvas.width = WORLD_WIDTH * CANVAS_SCALE_FACTOR;
+canvas.height = WORLD_HEIGHT * CANVAS_SCALE_FACTOR;
 const ctx = canvas.getContext('2d') as CanvasRenderingContext2D;
 
-export let canvasRender = ptr => console.warn('`canvasRender` called before loaded');
+export let canvasRender = (ptr: number, dirtyRects: Uint32Array) =>
+  console.warn('`canvasRender` called before loaded');
 
 let paused = true;
 let innerTick;
+/** Number of generations simulated between each rendered frame while running */
+let generationsPerFrame = 1;
+
+export const setGenerationsPerFrame = (generations: number) => {
+  generationsPerFrame = Math.max(1, Math.floor(generations));
+};
+
+/** State that clicked cells are set to, like Wireworld's conductors */
+let paintState = 1;
+
+export const setPaintState = (state: number) => {
+  paintState = state;
+};
+
+/** The cell that was last clicked, which is where the clipboard gets pasted */
+export let lastClickedCell = { x: 10, y: 10 };
 
 export const pause = () => {
   paused = true;
@@ -22,18 +41,20 @@ export const resume = () => {
   innerTick();
 };
 
-const getBoardCoords = (canvas: HTMLCanvasElement, e: MouseEvent): { x: number; y: number } => {
+const getCanvasCoords = (canvas: HTMLCanvasElement, e: MouseEvent): { x: number; y: number } => {
   const rect = canvas.getBoundingClientRect();
   return {
-    x: Math.floor((e.clientX - rect.left) / CANVAS_SCALE_FACTOR),
-    y: Math.floor((e.clientY - rect.top) / CANVAS_SCALE_FACTOR),
+    x: Math.floor(e.clientX - rect.left),
+    y: Math.floor(e.clientY - rect.top),
   };
 };
 
 wasm.then(async engine => {
   const wasmShim = await asyncWasmShim;
 
-  canvasRender = (ptr: number) => {
+  // `dirtyRects` holds `[x, y, width, height]` of each area of the canvas that changed since the
+  // last render, so only those parts of the buffer need to be uploaded.
+  canvasRender = (ptr: number, dirtyRects: Uint32Array) => {
     let buf;
     try {
       buf = new Uint8ClampedArray(wasmShim.getWasmBuf(), ptr, canvas.height * canvas.width * 4);
@@ -43,48 +64,93 @@ wasm.then(async engine => {
       return;
     }
 
-    const imageData = new ImageData(buf, canvas.height, canvas.width);
-    ctx.putImageData(imageData, 0, 0);
+    const imageData = new ImageData(buf, canvas.width, canvas.height);
+    for (let i = 0; i < dirtyRects.length; i += 4) {
+      const [x, y, width, height] = dirtyRects.subarray(i, i + 4);
+      ctx.putImageData(imageData, 0, 0, x, y, width, height);
+    }
     if (!paused) {
       setTimeout(() => requestAnimationFrame(innerTick), 0);
     }
   };
 
+  // Cells on the hexagonal and triangular grids aren't squares, so the engine maps canvas pixels to
+  // cells.  Returns -1 if there's no cell under the mouse.
+  const getCellIndex = (e: MouseEvent): number => {
+    const { x, y } = getCanvasCoords(canvas, e);
+    return engine.get_cell_at_canvas_pixel(x, y);
+  };
+  const toggleCell = (i: number) => {
+    const [x, y] = [i % WORLD_WIDTH, Math.floor(i / WORLD_WIDTH)];
+    if (paintState === 1) {
+      engine.set_pixel(x, y);
+    } else {
+      engine.set_pixel_state(x, y, paintState);
+    }
+  };
+
   let mouseDown = false;
   let alreadySetValues = new Set();
+  // Dragging with shift held selects a rectangle of cells instead of toggling them
+  let selectionStart: number | null = null;
   canvas.onmousedown = e => {
-    const { x, y } = getBoardCoords(canvas, e);
-    engine.set_pixel(x, y);
-    alreadySetValues.add(`${x},${y}`);
+    const i = getCellIndex(e);
+    if (i >= 0) {
+      lastClickedCell = { x: i % WORLD_WIDTH, y: Math.floor(i / WORLD_WIDTH) };
+    }
+    if (e.shiftKey) {
+      selectionStart = i >= 0 ? i : null;
+      if (selectionStart === null) {
+        engine.deselect();
+      }
+      return;
+    }
+
+    if (i >= 0) {
+      toggleCell(i);
+      alreadySetValues.add(i);
+    }
     mouseDown = true;
   };
 
   canvas.onmouseup = () => {
     mouseDown = false;
+    selectionStart = null;
     alreadySetValues.clear();
   };
 
   canvas.onmousemove = e => {
+    if (selectionStart !== null) {
+      const i = getCellIndex(e);
+      if (i >= 0) {
+        engine.set_selection(
+          selectionStart % WORLD_WIDTH,
+          Math.floor(selectionStart / WORLD_WIDTH),
+          i % WORLD_WIDTH,
+          Math.floor(i / WORLD_WIDTH)
+        );
+      }
+      return;
+    }
     if (!mouseDown) {
       return;
     }
 
-    // Prevent us toggling pixels back when a mouse moves within the same board square
-    const { x, y } = getBoardCoords(canvas, e);
-    const cacheEntry = `${x},${y}`;
-    if (alreadySetValues.has(cacheEntry)) {
+    // Prevent us toggling pixels back when a mouse moves within the same board cell
+    const i = getCellIndex(e);
+    if (i < 0 || alreadySetValues.has(i)) {
       return;
     }
-    alreadySetValues.add(cacheEntry);
+    alreadySetValues.add(i);
 
-    engine.set_pixel(x, y);
+    toggleCell(i);
   };
 
   console.log('Engine loaded');
-  engine.init();
+  engine.init(WORLD_WIDTH, WORLD_HEIGHT, CANVAS_SCALE_FACTOR);
 
   innerTick = () => {
-    engine.tick();
+    engine.tick_n(generationsPerFrame);
   };
 
   ReactDOM.render(<App engine={engine} />, document.getElementById('root'));
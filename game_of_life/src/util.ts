@@ -1,7 +1,13 @@
-export const WORLD_WIDTH = 150;
-export const WORLD_HEIGHT = 150;
+const params = new URLSearchParams(window.location.search);
+const getParam = (name: string, defaultValue: number): number => {
+  const value = parseInt(params.get(name) || '', 10);
+  return Number.isNaN(value) || value <= 0 ? defaultValue : value;
+};
+
+export const WORLD_WIDTH = getParam('width', 150);
+export const WORLD_HEIGHT = getParam('height', 150);
 export const CELL_COUNT = WORLD_HEIGHT * WORLD_WIDTH;
-export const CANVAS_SCALE_FACTOR = 6;
+export const CANVAS_SCALE_FACTOR = getParam('scale', 6);
 
 export const getIndex = (x: number, y: number): number => {
   return y * WORLD_WIDTH + x;